@@ -7,19 +7,24 @@ use bevy_xpbd_3d::prelude::*;
 
 use crate::GameState;
 
-pub struct AssetPlugin;
+pub struct AssetPlugin {
+    /// Whether to write every registered type to `assets/registry.json` for the Blender addon.
+    /// Off for headless replays, which leave out the camera and UI plugins and would export an
+    /// incomplete registry.
+    pub export_registry: bool,
+}
 
 impl Plugin for AssetPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((
-            ExportRegistryPlugin::default(),
-            BlueprintsPlugin {
-                library_folder: "scenes/library".into(),
-                format: GltfFormat::GLB,
-                legacy_mode: false,
-                ..Default::default()
-            },
-        ))
+        if self.export_registry {
+            app.add_plugins(ExportRegistryPlugin::default());
+        }
+        app.add_plugins(BlueprintsPlugin {
+            library_folder: "scenes/library".into(),
+            format: GltfFormat::GLB,
+            legacy_mode: false,
+            ..Default::default()
+        })
         .add_loading_state(
             LoadingState::new(GameState::Preload)
                 .continue_to_state(GameState::Overworld)
@@ -0,0 +1,97 @@
+use super::{cutaway::CutAway, MainCamera};
+use crate::physics::collision::CollisionLayer;
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::*;
+
+/// Height above the target the camera keeps a clear line to, roughly a character's head
+const FOCUS_HEIGHT: f32 = 1.0;
+
+/// Keeps the camera out of walls and props by sphere-casting from the player to where the camera
+/// wants to be. Anything that's been [`CutAway`] is see-through, so the camera is free to pass it.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraCollision {
+    pub probe_radius: f32,
+    /// How quickly the camera eases back out once it's no longer blocked
+    pub ease_out: f32,
+    /// The distance from the player the camera is currently allowed to be at
+    allowed_distance: Option<f32>,
+}
+
+impl Default for CameraCollision {
+    fn default() -> Self {
+        CameraCollision {
+            probe_radius: 0.4,
+            ease_out: 3.0,
+            allowed_distance: None,
+        }
+    }
+}
+
+impl CameraCollision {
+    pub fn focus(player_position: Vec3) -> Vec3 {
+        player_position + Vec3::Y * FOCUS_HEIGHT
+    }
+
+    /// Pulls `position` in toward the player if it is further out than the camera is allowed
+    pub fn clamp(&self, focus: Vec3, position: Vec3) -> Vec3 {
+        match self.allowed_distance {
+            Some(allowed) => focus + (position - focus).clamp_length_max(allowed),
+            None => position,
+        }
+    }
+}
+
+pub(super) fn add_camera_collision(
+    mut commands: Commands,
+    camera_query: Query<Entity, (With<MainCamera>, Without<CameraCollision>)>,
+) {
+    for entity in &camera_query {
+        commands.entity(entity).insert(CameraCollision::default());
+    }
+}
+
+pub(super) fn avoid_camera_collisions(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    mut camera_query: Query<(&MainCamera, &mut CameraCollision)>,
+    cutaway_query: Query<Entity, With<CutAway>>,
+) {
+    for (camera, mut collision) in &mut camera_query {
+        let focus = CameraCollision::focus(camera.target.position);
+        let to_camera = camera.desired_position - focus;
+        let desired_distance = to_camera.length();
+        let Ok(direction) = Direction3d::new(to_camera) else {
+            continue;
+        };
+
+        let hit = spatial_query.cast_shape(
+            &Collider::sphere(collision.probe_radius),
+            focus,
+            Quat::default(),
+            direction,
+            desired_distance,
+            true,
+            SpatialQueryFilter::from_mask(CollisionLayer::camera_blocking_mask())
+                .with_excluded_entities(&cutaway_query),
+        );
+
+        collision.allowed_distance = match (hit, collision.allowed_distance) {
+            // Snap in as soon as something is in the way so the camera never ends up inside it
+            (Some(hit), _) => Some(hit.time_of_impact),
+            (None, Some(allowed)) => {
+                let eased = allowed.lerp(
+                    desired_distance,
+                    (time.delta_seconds() * collision.ease_out).min(1.0),
+                );
+                if desired_distance - eased < 0.01 {
+                    None
+                } else {
+                    Some(eased)
+                }
+            }
+            (None, None) => None,
+        };
+    }
+}
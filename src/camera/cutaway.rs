@@ -0,0 +1,295 @@
+use super::collision::CameraCollision;
+use super::{position_camera, MainCamera};
+use crate::input::context::gameplay_running;
+use crate::physics::collision::CollisionLayer;
+use crate::player::Player;
+use crate::GameState;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_xpbd_3d::prelude::*;
+
+pub struct CutawayPlugin;
+
+impl Plugin for CutawayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CutawaySettings>()
+            .init_resource::<CutawayTargets>()
+            .register_type::<CutawaySettings>()
+            .register_type::<Roof>()
+            .add_systems(Update, make_roofs_sensors)
+            .add_systems(
+                PostUpdate,
+                (
+                    cut_away_roofs,
+                    cut_away_occluders.after(position_camera),
+                    fade_cutaways
+                        .after(cut_away_roofs)
+                        .after(cut_away_occluders),
+                )
+                    .run_if(in_state(GameState::Overworld))
+                    .run_if(gameplay_running),
+            );
+    }
+}
+
+/// An interior volume, e.g. the inside of the teahouse. While a player is inside it, the meshes
+/// parented to the volume in Blender (the roof, an upper floor) fade out so the camera can see in,
+/// and fade back in once everyone has left.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Roof;
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CutawaySettings {
+    /// How see-through a mesh between the camera and a player becomes, 0.0 hides it completely
+    pub occluder_alpha: f32,
+    /// How much of a mesh's opacity fades in or out each second
+    pub fade_speed: f32,
+    /// The most meshes a single camera will fade out to see its target
+    pub max_occluders: u32,
+}
+
+impl Default for CutawaySettings {
+    fn default() -> Self {
+        CutawaySettings {
+            occluder_alpha: 0.3,
+            fade_speed: 4.0,
+            max_occluders: 8,
+        }
+    }
+}
+
+/// A mesh that's been faded out of the camera's way. It's given its own copy of its material to
+/// fade, since glTF meshes share theirs, and gets the original back once it's fully visible again.
+#[derive(Component)]
+pub struct CutAway {
+    original: Handle<StandardMaterial>,
+    alpha: f32,
+    target_alpha: f32,
+}
+
+/// How see-through each mesh the cameras want out of the way should be this frame
+#[derive(Resource, Default)]
+struct CutawayTargets(HashMap<Entity, f32>);
+
+impl CutawayTargets {
+    /// Asks for `entity` to fade to `alpha`, the most see-through request wins
+    fn request(&mut self, entity: Entity, alpha: f32) {
+        let target = self.0.entry(entity).or_insert(alpha);
+        *target = target.min(alpha);
+    }
+}
+
+/// Moves `alpha` toward `target` by at most `step`
+fn fade_toward(alpha: f32, target: f32, step: f32) -> f32 {
+    if alpha < target {
+        (alpha + step).min(target)
+    } else {
+        (alpha - step).max(target)
+    }
+}
+
+fn make_roofs_sensors(mut commands: Commands, roof_query: Query<Entity, Added<Roof>>) {
+    for entity in &roof_query {
+        commands
+            .entity(entity)
+            .insert((Sensor, CollisionLayer::camera_zone_layers()));
+    }
+}
+
+fn cut_away_roofs(
+    collisions: Res<Collisions>,
+    player_query: Query<(), With<Player>>,
+    roof_query: Query<Entity, With<Roof>>,
+    children: Query<&Children>,
+    mesh_query: Query<(), With<Handle<StandardMaterial>>>,
+    mut targets: ResMut<CutawayTargets>,
+) {
+    for roof in &roof_query {
+        let occupied = collisions.collisions_with_entity(roof).any(|collision| {
+            player_query.contains(collision.entity1) || player_query.contains(collision.entity2)
+        });
+        if !occupied {
+            continue;
+        }
+
+        for child in children.iter_descendants(roof) {
+            if mesh_query.contains(child) {
+                targets.request(child, 0.0);
+            }
+        }
+    }
+}
+
+fn cut_away_occluders(
+    spatial_query: SpatialQuery,
+    settings: Res<CutawaySettings>,
+    camera_query: Query<&MainCamera>,
+    children: Query<&Children>,
+    mesh_query: Query<(), With<Handle<StandardMaterial>>>,
+    mut targets: ResMut<CutawayTargets>,
+) {
+    let mask = LayerMask::from([
+        CollisionLayer::Terrain,
+        CollisionLayer::Object,
+        CollisionLayer::Vehicle,
+    ]);
+
+    for camera in &camera_query {
+        // Where the camera wants to be, not where collision has pulled it in to, otherwise the
+        // wall it was pulled in front of is never between them
+        let focus = CameraCollision::focus(camera.target.position);
+        let to_camera = camera.desired_position - focus;
+        let Ok(direction) = Direction3d::new(to_camera) else {
+            continue;
+        };
+
+        let hits = spatial_query.ray_hits(
+            focus,
+            direction,
+            to_camera.length(),
+            settings.max_occluders,
+            true,
+            SpatialQueryFilter::from_mask(mask),
+        );
+        // Colliders usually sit on a parent of the meshes they were made for
+        for hit in hits {
+            for entity in std::iter::once(hit.entity).chain(children.iter_descendants(hit.entity)) {
+                if mesh_query.contains(entity) {
+                    targets.request(entity, settings.occluder_alpha);
+                }
+            }
+        }
+    }
+}
+
+fn fade_cutaways(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<CutawaySettings>,
+    mut targets: ResMut<CutawayTargets>,
+    mut cutaway_query: Query<(Entity, &mut CutAway, &mut Handle<StandardMaterial>)>,
+    new_query: Query<&Handle<StandardMaterial>, Without<CutAway>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let step = time.delta_seconds() * settings.fade_speed;
+
+    for (entity, mut cutaway, mut material) in &mut cutaway_query {
+        cutaway.target_alpha = targets.0.remove(&entity).unwrap_or(1.0);
+        cutaway.alpha = fade_toward(cutaway.alpha, cutaway.target_alpha, step);
+
+        // Fully back in view, so it can go back to sharing its original material
+        if cutaway.alpha >= 1.0 {
+            *material = cutaway.original.clone();
+            commands.entity(entity).remove::<CutAway>();
+            continue;
+        }
+
+        let Some(original_alpha) = materials
+            .get(&cutaway.original)
+            .map(|original| original.base_color.a())
+        else {
+            continue;
+        };
+        if let Some(faded) = materials.get_mut(material.id()) {
+            faded.base_color.set_a(original_alpha * cutaway.alpha);
+        }
+    }
+
+    for (entity, alpha) in targets.0.drain() {
+        let Ok(original) = new_query.get(entity) else {
+            continue;
+        };
+        let Some(mut faded) = materials.get(original).cloned() else {
+            continue;
+        };
+        faded.alpha_mode = AlphaMode::Blend;
+
+        commands.entity(entity).insert((
+            CutAway {
+                original: original.clone(),
+                alpha: 1.0,
+                target_alpha: alpha,
+            },
+            materials.add(faded),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutaways_fade_toward_the_most_see_through_request() {
+        let entity = Entity::from_raw(1);
+        let mut targets = CutawayTargets::default();
+        targets.request(entity, 0.3);
+        targets.request(entity, 0.0);
+        targets.request(entity, 0.5);
+        assert_eq!(targets.0[&entity], 0.0);
+
+        assert_eq!(fade_toward(1.0, 0.3, 0.25), 0.75);
+        assert_eq!(fade_toward(0.4, 0.3, 0.25), 0.3);
+        assert_eq!(fade_toward(0.3, 1.0, 0.5), 0.8);
+        assert_eq!(fade_toward(0.8, 1.0, 0.5), 1.0);
+    }
+
+    #[test]
+    fn colliders_between_the_camera_and_player_are_cut_away() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin::default(),
+            bevy::scene::ScenePlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<CutawaySettings>()
+        .init_resource::<CutawayTargets>()
+        .add_systems(Update, (cut_away_occluders, fade_cutaways).chain());
+
+        let material = app
+            .world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial::default());
+        let mesh = app
+            .world
+            .spawn((material.clone(), SpatialBundle::default()))
+            .id();
+        let beside = app.world.spawn((material, SpatialBundle::default())).id();
+        // A wall between the two, with its mesh as a child like a glTF scene
+        app.world
+            .spawn((
+                Collider::cuboid(4.0, 4.0, 0.5),
+                CollisionLayer::object_layers(),
+                SpatialBundle::from_transform(Transform::from_xyz(0.0, 1.0, 5.0)),
+            ))
+            .add_child(mesh);
+        app.world
+            .spawn((
+                Collider::cuboid(4.0, 4.0, 0.5),
+                CollisionLayer::object_layers(),
+                SpatialBundle::from_transform(Transform::from_xyz(20.0, 1.0, 5.0)),
+            ))
+            .add_child(beside);
+
+        let mut camera = MainCamera::default();
+        camera.target.position = Vec3::ZERO;
+        camera.desired_position = Vec3::new(0.0, 1.0, 10.0);
+        app.world.spawn(camera);
+
+        // The spatial query pipeline picks the colliders up on the first update
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert!(app.world.get::<CutAway>(mesh).is_some());
+        assert!(app.world.get::<CutAway>(beside).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use super::transition::{CameraTransition, Easing};
+use super::{pixel::PixelViewport, CameraData, CameraMode, MainCamera};
+use crate::input::{slots::PlayerSlot, InputBuffer, PlayerAction};
+use crate::player::Player;
+
+use bevy::prelude::*;
+
+/// Targets have to be at least this far in front of the camera to be picked, as the cosine of the
+/// angle from the camera's forward direction
+const LOCK_ON_CONE: f32 = 0.5;
+/// Eases the camera's look over to a new target, or back to the player on release
+const LOCK_ON_TRANSITION: CameraTransition = CameraTransition::new(0.35, Easing::EaseOut);
+
+/// Marks an entity the camera can lock on to
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Targetable;
+
+fn release_lock(camera: &mut MainCamera) {
+    camera.start_transition(LOCK_ON_TRANSITION);
+    camera.camera_mode = camera.mode_before_lock;
+    camera.lock_target = None;
+    camera.lock_position = None;
+}
+
+/// Every target in front of the camera and within reach of the player, with how far to the right
+/// of the camera it is
+fn targets_in_front(
+    camera: &MainCamera,
+    camera_data: &CameraData,
+    player_position: Vec3,
+    target_query: &Query<(Entity, &GlobalTransform), With<Targetable>>,
+) -> Vec<(Entity, Vec3, f32)> {
+    let camera_transform = Transform::from_translation(camera_data.camera_position)
+        .with_rotation(camera_data.camera_rotation);
+
+    target_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation()))
+        .filter(|(_, position)| position.distance(player_position) <= camera.lock_on_distance)
+        .filter_map(|(entity, position)| {
+            let to_target = (position - camera_transform.translation).normalize_or_zero();
+            (to_target.dot(*camera_transform.forward()) >= LOCK_ON_CONE)
+                .then(|| (entity, position, to_target.dot(*camera_transform.right())))
+        })
+        .collect()
+}
+
+pub(super) fn toggle_lock_on(
+    mut camera_query: Query<(&mut MainCamera, &CameraData, &PixelViewport)>,
+    mut player_query: Query<(&mut InputBuffer, &PlayerSlot, &Transform), With<Player>>,
+    target_query: Query<(Entity, &GlobalTransform), With<Targetable>>,
+) {
+    for (mut camera, camera_data, viewport) in &mut camera_query {
+        // Each camera locks on for the player whose viewport it draws
+        let Some((mut buffer, _, player_transform)) = player_query
+            .iter_mut()
+            .find(|(_, slot, _)| slot.0 == viewport.0)
+        else {
+            continue;
+        };
+        let player_position = player_transform.translation;
+
+        if buffer.consume(PlayerAction::LockOn) {
+            if camera.lock_target.is_some() {
+                release_lock(&mut camera);
+                continue;
+            }
+
+            let nearest = targets_in_front(&camera, camera_data, player_position, &target_query)
+                .into_iter()
+                .min_by(|(_, a, _), (_, b, _)| {
+                    a.distance_squared(player_position)
+                        .total_cmp(&b.distance_squared(player_position))
+                });
+
+            if let Some((entity, position, _)) = nearest {
+                camera.start_transition(LOCK_ON_TRANSITION);
+                camera.mode_before_lock = camera.camera_mode;
+                camera.camera_mode = CameraMode::LockOn;
+                camera.lock_target = Some(entity);
+                camera.lock_position = Some(position);
+            }
+        }
+
+        if let Some(current) = camera.lock_target {
+            if buffer.consume(PlayerAction::CycleTarget) {
+                let mut targets =
+                    targets_in_front(&camera, camera_data, player_position, &target_query);
+                targets.sort_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+                // Step to the next target to the right, wrapping around to the leftmost one
+                let current_index = targets.iter().position(|(entity, _, _)| *entity == current);
+                let next = match current_index {
+                    Some(index) => targets.get((index + 1) % targets.len()),
+                    None => targets.first(),
+                };
+
+                if let Some((entity, position, _)) = next {
+                    camera.start_transition(LOCK_ON_TRANSITION);
+                    camera.lock_target = Some(*entity);
+                    camera.lock_position = Some(*position);
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn update_lock_on(
+    time: Res<Time>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+    player_query: Query<(&Transform, &PlayerSlot), With<Player>>,
+    target_query: Query<&GlobalTransform, With<Targetable>>,
+) {
+    for (mut camera, viewport) in &mut camera_query {
+        let Some(target) = camera.lock_target else {
+            continue;
+        };
+        let Some((player_transform, _)) =
+            player_query.iter().find(|(_, slot)| slot.0 == viewport.0)
+        else {
+            continue;
+        };
+        let player_position = player_transform.translation;
+
+        let target_position = match target_query.get(target) {
+            Ok(transform) => transform.translation(),
+            Err(_) => {
+                release_lock(&mut camera);
+                continue;
+            }
+        };
+
+        if target_position.distance(player_position) > camera.break_lock_distance {
+            release_lock(&mut camera);
+            continue;
+        }
+
+        camera.lock_position = Some(target_position);
+
+        // Swing round behind the player, on the far side from the target
+        let away = (player_position - target_position).normalize_or_zero();
+        if away.xz() != Vec2::ZERO {
+            let behind_angle = (-away.x).atan2(-away.z).to_degrees();
+            let difference = (behind_angle - camera.angle + 180.0).rem_euclid(360.0) - 180.0;
+            camera.angle += difference * (time.delta_seconds() * camera.follow_lag).min(1.0);
+        }
+    }
+}
@@ -0,0 +1,634 @@
+use crate::input::{
+    context::{gameplay_running, InputContextStack},
+    slots::{PlayerSlot, PlayerSlots},
+    InputBuffer, PlayerAction,
+};
+use crate::GameState;
+
+use bevy::prelude::*;
+
+use leafwing_input_manager::prelude::ActionState;
+
+mod collision;
+pub mod cutaway;
+pub mod lock_on;
+pub mod photo;
+pub mod pixel;
+pub mod profile;
+pub mod rail;
+pub mod shake;
+pub mod target;
+pub mod transition;
+pub mod zone;
+
+use collision::CameraCollision;
+use lock_on::Targetable;
+use pixel::{
+    Canvas, OuterCamera, PixelCameraConfiguration, PixelCameraPlugin, PixelViewport, TexelSnapSet,
+    RES_HEIGHT, RES_WIDTH,
+};
+use profile::{CameraProfile, CameraProfileLoader};
+use rail::{ActiveRail, CameraRail, CameraRailEvent, RailDrive, RailPoint, RailTrigger};
+use shake::{CameraShake, CameraTrauma};
+use target::{CameraTarget, TargetFrame};
+use transition::{ActiveTransition, CameraTransition, CameraTransitionEvent, Easing};
+use zone::{CameraZone, ZoneBlend};
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Msaa::Off)
+            .add_plugins(PixelCameraPlugin::<MainCamera, OuterCamera, Canvas>::new(
+                RES_WIDTH, RES_HEIGHT,
+            ))
+            .add_plugins(TraditionalCameraPlugin)
+            .add_plugins(photo::PhotoModePlugin)
+            .add_plugins(cutaway::CutawayPlugin)
+            .init_resource::<CameraControlSettings>()
+            .register_type::<MainCamera>()
+            .register_type::<CameraData>()
+            .register_type::<CameraControlSettings>();
+    }
+}
+
+struct TraditionalCameraPlugin;
+
+/// Switching between [`CameraMode`]s
+const MODE_TRANSITION: CameraTransition = CameraTransition::new(0.5, Easing::EaseInOut);
+/// Turning by a 45 degree step in [`CameraMode::Fixed`]
+const FIXED_TURN_TRANSITION: CameraTransition = CameraTransition::new(0.3, Easing::EaseOut);
+
+impl Plugin for TraditionalCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraTrauma>()
+            .add_event::<CameraRailEvent>()
+            .add_event::<CameraTransitionEvent>()
+            .register_type::<CameraCollision>()
+            .register_type::<CameraZone>()
+            .register_type::<CameraMode>()
+            .register_type::<Option<CameraMode>>()
+            .register_type::<Option<Vec3>>()
+            .register_type::<CameraShake>()
+            .register_type::<Targetable>()
+            .register_type::<CameraRail>()
+            .register_type::<RailPoint>()
+            .register_type::<RailTrigger>()
+            .register_type::<RailDrive>()
+            .register_type::<CameraTarget>()
+            .init_asset::<CameraProfile>()
+            .init_asset_loader::<CameraProfileLoader>()
+            .add_systems(Startup, profile::load_camera_profile)
+            .add_systems(
+                Update,
+                (
+                    collision::add_camera_collision,
+                    zone::make_zones_sensors,
+                    rail::make_rail_triggers_sensors,
+                    shake::add_camera_shake,
+                    shake::shake_on_landing.before(shake::add_trauma),
+                    shake::add_trauma,
+                    split_screen_for_players,
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    target::gather_camera_targets
+                        .before(follow_player)
+                        .before(profile::apply_camera_profile)
+                        .before(update_camera_desired_position)
+                        .before(collision::avoid_camera_collisions)
+                        .before(rail::follow_camera_rails),
+                    lock_on::toggle_lock_on.before(lock_on::update_lock_on),
+                    lock_on::update_lock_on
+                        .after(rotate_camera)
+                        .before(update_camera_desired_position),
+                    zone::detect_camera_zones.before(zone::blend_camera_zones),
+                    zone::blend_camera_zones
+                        .after(rotate_camera)
+                        .after(profile::apply_camera_profile)
+                        .after(follow_player)
+                        .before(update_camera_desired_position),
+                    follow_player.before(update_camera_desired_position),
+                    transition::start_camera_transitions
+                        .after(rotate_camera)
+                        .before(update_camera_desired_position),
+                    update_camera_desired_position,
+                    collision::avoid_camera_collisions
+                        .after(update_camera_desired_position)
+                        .before(position_camera),
+                    rail::trigger_camera_rails.before(rail::start_camera_rails),
+                    rail::start_camera_rails.before(rail::follow_camera_rails),
+                    rail::follow_camera_rails
+                        .after(collision::avoid_camera_collisions)
+                        .before(position_camera),
+                    position_camera,
+                    shake::apply_camera_shake.after(position_camera),
+                    orbit_camera.before(rotate_camera),
+                    rotate_camera,
+                    profile::apply_camera_profile,
+                )
+                    .after(bevy_xpbd_3d::PhysicsSet::Sync)
+                    .before(TexelSnapSet)
+                    .before(bevy::transform::TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Overworld))
+                    .run_if(gameplay_running),
+            );
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct MainCamera {
+    offset: Vec3,
+    angle: f32,
+    pitch: f32,
+    pitch_max: f32,
+    pitch_min: f32,
+    follow_lag: f32,
+    recenter_delay: f32,
+    time_since_orbit: f32,
+    easing: f32,
+    camera_mode: CameraMode,
+    desired_position: Vec3,
+    /// The [`CameraTarget`]s being followed
+    target: TargetFrame,
+    /// Where the camera actually is before any shake is applied to its [`Transform`]
+    position: Vec3,
+    /// The point the camera wants to look at
+    focus: Vec3,
+    /// How far ahead of the player the camera is currently leading
+    look_ahead: Vec3,
+    /// Seconds of the player's velocity to lead by
+    look_ahead_time: f32,
+    look_ahead_max: f32,
+    look_ahead_smoothing: f32,
+    /// The point the camera is looking at, which trails `focus` while blending on or off a rail
+    look_point: Vec3,
+    transition: Option<ActiveTransition>,
+    /// What the camera showed last frame once any transition was blended in, which is where a
+    /// new transition starts from
+    shown_position: Vec3,
+    shown_look: Vec3,
+    rail: Option<ActiveRail>,
+    rail_blend: f32,
+    lock_target: Option<Entity>,
+    lock_position: Option<Vec3>,
+    mode_before_lock: CameraMode,
+    /// How close a target has to be to the player to lock on to it
+    lock_on_distance: f32,
+    /// How far a locked target can get from the player before the lock breaks
+    break_lock_distance: f32,
+    zone: Option<CameraZone>,
+    zone_blend_time: f32,
+    blend: Option<ZoneBlend>,
+}
+
+impl Default for MainCamera {
+    fn default() -> Self {
+        MainCamera {
+            offset: Vec3::new(0.0, 6.5, 10.0),
+            angle: 0.0,
+            pitch: 0.0,
+            pitch_max: 35.0,
+            pitch_min: -25.0,
+            follow_lag: 1.5,
+            recenter_delay: 1.0,
+            time_since_orbit: 0.0,
+            easing: 2.0,
+            camera_mode: CameraMode::Free,
+            desired_position: Vec3::ZERO,
+            target: TargetFrame::default(),
+            position: Vec3::ZERO,
+            focus: Vec3::ZERO,
+            look_ahead: Vec3::ZERO,
+            look_ahead_time: 0.3,
+            look_ahead_max: 4.0,
+            look_ahead_smoothing: 1.5,
+            look_point: Vec3::ZERO,
+            transition: None,
+            shown_position: Vec3::ZERO,
+            shown_look: Vec3::ZERO,
+            rail: None,
+            rail_blend: 0.0,
+            lock_target: None,
+            lock_position: None,
+            mode_before_lock: CameraMode::Free,
+            lock_on_distance: 20.0,
+            break_lock_distance: 30.0,
+            zone: None,
+            zone_blend_time: 1.0,
+            blend: None,
+        }
+    }
+}
+
+impl MainCamera {
+    /// The mode in effect, which a [`CameraZone`] can override unless the camera is locked on
+    fn mode(&self) -> CameraMode {
+        if self.camera_mode == CameraMode::LockOn {
+            return CameraMode::LockOn;
+        }
+        self.zone
+            .as_ref()
+            .and_then(|zone| zone.mode)
+            .unwrap_or(self.camera_mode)
+    }
+
+    /// True while a [`CameraZone`] is holding the camera at a set angle
+    fn angle_locked(&self) -> bool {
+        self.mode() != CameraMode::LockOn
+            && self.zone.as_ref().is_some_and(|zone| zone.angle.is_some())
+    }
+
+    fn current_offset(&self) -> Vec3 {
+        self.blend.map_or(self.offset, |blend| blend.offset)
+    }
+
+    fn current_angle(&self) -> f32 {
+        self.blend.map_or(self.angle, |blend| blend.angle)
+    }
+
+    fn current_easing(&self) -> f32 {
+        self.blend.map_or(self.easing, |blend| blend.easing)
+    }
+
+    /// Starts blending from what the camera is showing now, call this before changing the mode or
+    /// angle so the change eases in instead of snapping
+    pub fn start_transition(&mut self, transition: CameraTransition) {
+        self.transition = Some(ActiveTransition::new(
+            transition,
+            self.shown_position,
+            self.shown_look,
+        ));
+    }
+
+    fn transition_to_mode(&mut self, mode: CameraMode, transition: CameraTransition) {
+        if mode != self.camera_mode {
+            self.start_transition(transition);
+            self.camera_mode = mode;
+        }
+    }
+}
+
+/// How [`PlayerAction::CamOrbit`] turns the camera in [`CameraMode::Free`] and
+/// [`CameraMode::Follow`]
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CameraControlSettings {
+    /// Degrees per second at full stick tilt
+    pub stick_sensitivity: Vec2,
+    /// Degrees per pixel of mouse motion
+    pub mouse_sensitivity: Vec2,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    /// Give every joined player a viewport of their own instead of framing them all in one
+    pub split_screen: bool,
+}
+
+impl CameraControlSettings {
+    /// Degrees to turn the camera by for one frame of orbit input, x around the player and y
+    /// raising the camera. Mouse motion is already a per frame delta with y pointing down the
+    /// screen, stick tilt is a rate with y pointing up. They come from separate bindings so one
+    /// player's stick never changes how another's mouse is scaled.
+    pub fn orbit_delta(&self, stick: Vec2, mouse: Vec2, delta_seconds: f32) -> Vec2 {
+        let delta = stick * self.stick_sensitivity * delta_seconds
+            + Vec2::new(mouse.x, -mouse.y) * self.mouse_sensitivity;
+        let x_sign = if self.invert_x { -1.0 } else { 1.0 };
+        let y_sign = if self.invert_y { 1.0 } else { -1.0 };
+        Vec2::new(delta.x * x_sign, delta.y * y_sign)
+    }
+}
+
+impl Default for CameraControlSettings {
+    fn default() -> Self {
+        CameraControlSettings {
+            stick_sensitivity: Vec2::new(180.0, 90.0),
+            mouse_sensitivity: Vec2::new(0.25, 0.15),
+            invert_x: false,
+            invert_y: false,
+            split_screen: false,
+        }
+    }
+}
+
+/// Where a camera is looking, kept on each primary camera for its own viewport. The resource
+/// mirrors the first viewport's camera.
+#[derive(Resource, Component, Reflect, Default, Clone)]
+#[reflect(Resource, Component)]
+pub struct CameraData {
+    pub camera_position: Vec3,
+    pub camera_rotation: Quat,
+    pub camera_id: Option<Entity>,
+    pub lock_on_target: Option<Vec3>,
+}
+
+impl CameraData {
+    pub fn translate_direction_in_camera_space(&self, x: f32, z: f32) -> Vec3 {
+        let camera_transform =
+            Transform::from_translation(self.camera_position).with_rotation(self.camera_rotation);
+
+        let mut forward = *camera_transform.forward();
+        forward.y = 0.0;
+
+        let mut right = *camera_transform.right();
+        right.y = 0.0;
+
+        let right_vec: Vec3 = x * right;
+        let forward_vec: Vec3 = z * forward;
+
+        right_vec + forward_vec
+    }
+
+    /// Like [`CameraData::translate_direction_in_camera_space`], but while the camera is locked on
+    /// forward points from `player_position` to the target, so sideways input strafes around it
+    pub fn translate_direction_for_player(&self, x: f32, z: f32, player_position: Vec3) -> Vec3 {
+        let Some(target) = self.lock_on_target else {
+            return self.translate_direction_in_camera_space(x, z);
+        };
+
+        let forward = Vec3::new(
+            target.x - player_position.x,
+            0.0,
+            target.z - player_position.z,
+        )
+        .normalize_or_zero();
+        if forward == Vec3::ZERO {
+            return self.translate_direction_in_camera_space(x, z);
+        }
+        let right = forward.cross(Vec3::Y);
+
+        x * right + z * forward
+    }
+}
+
+#[derive(Default, Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    #[default]
+    Fixed,
+    Free,
+    Follow,
+    LockOn,
+}
+
+impl CameraMode {
+    fn shift_up(&self) -> CameraMode {
+        match self {
+            CameraMode::Fixed => CameraMode::Free,
+            CameraMode::Free => CameraMode::Free,
+            CameraMode::Follow => CameraMode::Free,
+            CameraMode::LockOn => CameraMode::LockOn,
+        }
+    }
+    fn shift_down(&self) -> CameraMode {
+        match self {
+            CameraMode::Fixed => CameraMode::Follow,
+            CameraMode::Free => CameraMode::Follow,
+            CameraMode::Follow => CameraMode::Follow,
+            CameraMode::LockOn => CameraMode::LockOn,
+        }
+    }
+}
+
+fn update_camera_desired_position(time: Res<Time>, mut camera_query: Query<&mut MainCamera>) {
+    for mut camera in &mut camera_query {
+        // Lead ahead of the target in the direction they're running, so they don't run out of
+        // frame at full speed
+        let velocity = camera.target.velocity;
+        let lead = (Vec3::new(velocity.x, 0.0, velocity.z) * camera.look_ahead_time)
+            .clamp_length_max(camera.look_ahead_max);
+        camera.look_ahead = camera.look_ahead.lerp(
+            lead,
+            (time.delta_seconds() * camera.look_ahead_smoothing).min(1.0),
+        );
+        let center = camera.target.position + camera.look_ahead;
+
+        let mut starting_transform = Transform::from_translation(center);
+
+        starting_transform.rotation = Quat::default();
+        starting_transform.rotate_y(camera.current_angle().to_radians());
+        // Pitching rotates the offset around the player, raising the camera for positive pitch,
+        // and the offset stretches to fit a spread out group of targets in frame
+        let offset = camera.current_offset() * camera.target.zoom;
+        let offset =
+            Quat::from_rotation_x(camera.pitch.to_radians()) * Vec3::new(0.0, offset.y, -offset.z);
+        camera.desired_position =
+            starting_transform.translation + starting_transform.rotation * offset;
+        // Locked on, the camera looks between the player and the target to keep both in frame
+        camera.focus = match (camera.mode(), camera.lock_position) {
+            (CameraMode::LockOn, Some(target)) => center.lerp(target, 0.35),
+            _ => center,
+        };
+    }
+}
+
+/// Swings the camera back behind the player while they move, unless they've orbited it
+/// themselves in the last `recenter_delay` seconds
+fn follow_player(time: Res<Time>, mut camera_query: Query<&mut MainCamera>) {
+    for mut camera in &mut camera_query {
+        // Outside of follow mode the timer is held full, so switching to it recentres right away
+        let (CameraMode::Follow, false) = (camera.mode(), camera.angle_locked()) else {
+            camera.time_since_orbit = camera.recenter_delay;
+            continue;
+        };
+
+        camera.time_since_orbit += time.delta_seconds();
+        let player_moving = camera.target.velocity.xz().length() > 0.5;
+        if camera.time_since_orbit < camera.recenter_delay || !player_moving {
+            continue;
+        }
+
+        let forward = camera.target.rotation * Vec3::NEG_Z;
+        let behind_angle = (-forward.x).atan2(-forward.z).to_degrees() + 180.0;
+        let difference = (behind_angle - camera.angle + 180.0).rem_euclid(360.0) - 180.0;
+        camera.angle += difference * (time.delta_seconds() * camera.follow_lag).min(1.0);
+    }
+}
+
+fn position_camera(
+    time: Res<Time>,
+    mut primary_camera_data: ResMut<CameraData>,
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut MainCamera,
+        &mut CameraData,
+        Option<&CameraCollision>,
+    )>,
+) {
+    for (mut transform, mut camera, mut camera_data, collision) in &mut camera_query {
+        let lerped_position = camera.position.lerp(
+            camera.desired_position,
+            time.delta_seconds() * camera.current_easing(),
+        );
+        // Rails are placed by hand, so they're trusted to stay out of walls
+        camera.position = match collision {
+            Some(collision) if camera.rail.is_none() => collision.clamp(
+                CameraCollision::focus(camera.target.position),
+                lerped_position,
+            ),
+            _ => lerped_position,
+        };
+
+        camera.look_point = if camera.rail_blend > 0.0 {
+            camera.rail_blend -= time.delta_seconds();
+            camera.look_point.lerp(
+                camera.focus,
+                (time.delta_seconds() * camera.current_easing() * 2.0).min(1.0),
+            )
+        } else {
+            camera.focus
+        };
+
+        let (position, look) = match camera.transition {
+            Some(active) => active.blend(camera.position, camera.look_point),
+            None => (camera.position, camera.look_point),
+        };
+        camera.shown_position = position;
+        camera.shown_look = look;
+        *transform = Transform::from_translation(position).looking_at(look, Vec3::Y);
+
+        if let Some(active) = camera.transition.as_mut() {
+            active.tick(time.delta_seconds());
+            if active.finished() {
+                camera.transition = None;
+            }
+        }
+
+        camera_data.camera_position = transform.translation;
+        camera_data.camera_rotation = transform.rotation;
+        camera_data.lock_on_target = camera.lock_position;
+        if camera_data.camera_id == primary_camera_data.camera_id {
+            *primary_camera_data = camera_data.clone();
+        }
+    }
+}
+
+fn rotate_camera(
+    time: Res<Time>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+    mut actions_query: Query<(&mut InputBuffer, &PlayerSlot)>,
+) {
+    for (mut camera, viewport) in &mut camera_query {
+        // Each camera only answers to the player whose viewport it draws
+        let Some((mut action, _)) = actions_query
+            .iter_mut()
+            .find(|(_, slot)| slot.0 == viewport.0)
+        else {
+            continue;
+        };
+
+        if action.consume(PlayerAction::CamModeChangePositive) {
+            let mode = camera.camera_mode.shift_up();
+            camera.transition_to_mode(mode, MODE_TRANSITION);
+        }
+        if action.consume(PlayerAction::CamModeChangeNegative) {
+            let mode = camera.camera_mode.shift_down();
+            camera.transition_to_mode(mode, MODE_TRANSITION);
+        }
+        match camera.mode() {
+            _ if camera.angle_locked() => (),
+            CameraMode::LockOn => (),
+            CameraMode::Fixed => {
+                let mut angle = camera.angle;
+                if action.consume(PlayerAction::CamRotateLeft) {
+                    angle -= 45.0;
+                }
+                if action.consume(PlayerAction::CamRotateRight) {
+                    angle += 45.0;
+                }
+
+                let angle_i16 = angle as i16;
+                let angle_difference = angle_i16 % 45;
+                let angle_change = if angle_difference <= 22 {
+                    -1 * angle_difference
+                } else {
+                    45 - angle_difference
+                };
+                let new_angle = (angle_i16 + angle_change) as f32;
+                // Swing round to the next step instead of cutting straight to it
+                if new_angle != camera.angle {
+                    camera.start_transition(FIXED_TURN_TRANSITION);
+                    camera.angle = new_angle;
+                }
+            }
+            CameraMode::Free | CameraMode::Follow => {
+                if action.pressed(PlayerAction::CamRotateLeft) {
+                    camera.angle -= 180.0 * time.delta_seconds();
+                    camera.time_since_orbit = 0.0;
+                }
+                if action.pressed(PlayerAction::CamRotateRight) {
+                    camera.angle += 180.0 * time.delta_seconds();
+                    camera.time_since_orbit = 0.0;
+                }
+            }
+        }
+
+        if camera.angle > 360.0 {
+            camera.angle -= 360.0;
+        }
+
+        if camera.angle < -360.0 {
+            camera.angle += 360.0;
+        }
+    }
+}
+
+/// Matches the number of viewports to the joined players while split screen is on
+fn split_screen_for_players(
+    settings: Res<CameraControlSettings>,
+    slots: Res<PlayerSlots>,
+    mut pixel_config: ResMut<PixelCameraConfiguration>,
+) {
+    if !settings.is_changed() && !slots.is_changed() {
+        return;
+    }
+
+    let viewports = if settings.split_screen {
+        slots.slots().count()
+    } else {
+        1
+    };
+    if pixel_config.viewports != viewports {
+        pixel_config.viewports = viewports;
+    }
+}
+
+fn orbit_camera(
+    time: Res<Time>,
+    settings: Res<CameraControlSettings>,
+    context: Res<InputContextStack>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+    actions_query: Query<(&ActionState<PlayerAction>, &PlayerSlot)>,
+) {
+    if !context.top().uses_player_actions() {
+        return;
+    }
+
+    for (mut camera, viewport) in &mut camera_query {
+        let Some((action, _)) = actions_query.iter().find(|(_, slot)| slot.0 == viewport.0) else {
+            continue;
+        };
+        let axis = |orbit| {
+            action
+                .axis_pair(&orbit)
+                .map_or(Vec2::ZERO, |axis_pair| axis_pair.xy())
+        };
+        let delta = settings.orbit_delta(
+            axis(PlayerAction::CamOrbit),
+            axis(PlayerAction::CamOrbitMouse),
+            time.delta_seconds(),
+        );
+
+        if camera.angle_locked() {
+            continue;
+        }
+        if let CameraMode::Free | CameraMode::Follow = camera.mode() {
+            if delta != Vec2::ZERO {
+                camera.time_since_orbit = 0.0;
+            }
+            camera.angle += delta.x;
+            camera.pitch = (camera.pitch + delta.y).clamp(camera.pitch_min, camera.pitch_max);
+        }
+    }
+}
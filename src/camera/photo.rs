@@ -0,0 +1,563 @@
+use super::pixel::{OuterCamera, PixelCameraConfiguration, PixelViewport};
+use super::{CameraControlSettings, CameraData, MainCamera};
+use crate::input::context::{gameplay_running, in_input_context, InputContext, InputContextStack};
+use crate::input::{slots::PlayerSlot, InputBuffer, PlayerAction};
+use crate::physics::collision::CollisionLayer;
+use crate::player::{Player, PlayerData};
+use crate::GameState;
+
+use bevy::prelude::*;
+use bevy::render::{
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension},
+    view::{screenshot::ScreenshotManager, ColorGrading},
+};
+use bevy::window::PrimaryWindow;
+use bevy_xpbd_3d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use std::path::PathBuf;
+
+const PHOTO_DIRECTORY: &str = "unnamed-rpg";
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<PhotoAction>::default())
+            .init_resource::<PhotoModeSettings>()
+            .register_type::<PhotoModeSettings>()
+            .add_systems(Startup, spawn_photo_listener)
+            .add_systems(
+                PreUpdate,
+                (
+                    toggle_photo_actions.before(bevy::input::InputSystem),
+                    pause_for_photo_mode,
+                )
+                    .run_if(resource_changed::<InputContextStack>),
+            )
+            .add_systems(
+                Update,
+                (
+                    enter_photo_mode
+                        .run_if(in_state(GameState::Overworld))
+                        .run_if(gameplay_running),
+                    (fly_photo_camera, capture_photo, exit_photo_mode)
+                        .chain()
+                        .run_if(in_input_context(InputContext::PhotoMode)),
+                ),
+            );
+    }
+}
+
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
+pub enum PhotoAction {
+    Move,
+    /// Right stick tilt, a rate
+    Look,
+    /// Mouse motion, a per frame delta
+    LookMouse,
+    Rise,
+    Sink,
+    ZoomIn,
+    ZoomOut,
+    RollLeft,
+    RollRight,
+    Filter,
+    Capture,
+    Exit,
+}
+
+/// Colour grades photo mode can cycle through
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PhotoFilter {
+    #[default]
+    None,
+    Mono,
+    Faded,
+    Vivid,
+}
+
+impl PhotoFilter {
+    fn next(self) -> PhotoFilter {
+        match self {
+            PhotoFilter::None => PhotoFilter::Mono,
+            PhotoFilter::Mono => PhotoFilter::Faded,
+            PhotoFilter::Faded => PhotoFilter::Vivid,
+            PhotoFilter::Vivid => PhotoFilter::None,
+        }
+    }
+
+    /// `base` with this filter laid over it
+    fn grade(self, base: ColorGrading) -> ColorGrading {
+        match self {
+            PhotoFilter::None => base,
+            PhotoFilter::Mono => ColorGrading {
+                post_saturation: 0.0,
+                ..base
+            },
+            PhotoFilter::Faded => ColorGrading {
+                gamma: base.gamma * 0.8,
+                post_saturation: base.post_saturation * 0.6,
+                ..base
+            },
+            PhotoFilter::Vivid => ColorGrading {
+                exposure: base.exposure + 0.3,
+                pre_saturation: base.pre_saturation * 1.4,
+                ..base
+            },
+        }
+    }
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PhotoModeSettings {
+    /// Units per second the camera flies at
+    pub move_speed: f32,
+    /// How far from the player the camera can fly
+    pub max_distance: f32,
+    /// Radius of the sphere cast that stops the camera flying through walls
+    pub probe_radius: f32,
+    /// Narrowest and widest field of view in degrees
+    pub fov_range: Vec2,
+    /// Degrees per second for zooming and rolling
+    pub zoom_speed: f32,
+    pub roll_speed: f32,
+    /// Saved photos are the canvas scaled up by this whole number
+    pub upscale: u32,
+}
+
+impl Default for PhotoModeSettings {
+    fn default() -> Self {
+        PhotoModeSettings {
+            move_speed: 6.0,
+            max_distance: 15.0,
+            probe_radius: 0.3,
+            fov_range: Vec2::new(20.0, 100.0),
+            zoom_speed: 30.0,
+            roll_speed: 45.0,
+            upscale: 4,
+        }
+    }
+}
+
+/// Put on the primary [`MainCamera`] while photo mode has detached it from the player, with
+/// everything needed to put the camera back afterwards
+#[derive(Component)]
+pub struct PhotoCamera {
+    yaw: f32,
+    pitch: f32,
+    roll: f32,
+    fov: f32,
+    filter: PhotoFilter,
+    /// Where the player was standing, the camera is kept within reach of it
+    anchor: Vec3,
+    restore_fov: f32,
+    restore_grading: ColorGrading,
+    restore_texel_snapping: bool,
+}
+
+#[derive(Component)]
+pub struct PhotoInputListener;
+
+fn photo_input_map() -> InputMap<PhotoAction> {
+    use PhotoAction::*;
+
+    let mut input_map = InputMap::new([
+        (Rise, KeyCode::KeyE),
+        (Sink, KeyCode::KeyQ),
+        (ZoomIn, KeyCode::Equal),
+        (ZoomOut, KeyCode::Minus),
+        (RollLeft, KeyCode::KeyZ),
+        (RollRight, KeyCode::KeyC),
+        (Filter, KeyCode::KeyF),
+        (Capture, KeyCode::Enter),
+        (Exit, KeyCode::Escape),
+    ]);
+    input_map
+        .insert_multiple([
+            (Rise, GamepadButtonType::RightTrigger),
+            (Sink, GamepadButtonType::LeftTrigger),
+            (ZoomIn, GamepadButtonType::RightTrigger2),
+            (ZoomOut, GamepadButtonType::LeftTrigger2),
+            (RollLeft, GamepadButtonType::DPadLeft),
+            (RollRight, GamepadButtonType::DPadRight),
+            (Filter, GamepadButtonType::North),
+            (Capture, GamepadButtonType::South),
+            (Exit, GamepadButtonType::East),
+        ])
+        .insert(Move, VirtualDPad::wasd())
+        .insert(Move, DualAxis::left_stick())
+        .insert(LookMouse, DualAxis::mouse_motion())
+        .insert(Look, DualAxis::right_stick());
+    input_map
+}
+
+fn spawn_photo_listener(mut commands: Commands) {
+    commands.spawn((
+        Name::from("Photo Mode Input"),
+        PhotoInputListener,
+        InputManagerBundle {
+            input_map: photo_input_map(),
+            ..default()
+        },
+    ));
+}
+
+fn toggle_photo_actions(
+    stack: Res<InputContextStack>,
+    mut toggle_actions: ResMut<ToggleActions<PhotoAction>>,
+) {
+    toggle_actions.enabled = stack.is_active(InputContext::PhotoMode);
+}
+
+/// Freezes the game, physics and animations included, while photo mode is open
+fn pause_for_photo_mode(
+    stack: Res<InputContextStack>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    if stack.is_active(InputContext::PhotoMode) {
+        virtual_time.pause();
+        physics_time.pause();
+    } else {
+        virtual_time.unpause();
+        physics_time.unpause();
+    }
+}
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    mut context: ResMut<InputContextStack>,
+    mut pixel_config: ResMut<PixelCameraConfiguration>,
+    camera_data: Res<CameraData>,
+    player_data: Res<PlayerData>,
+    mut player_query: Query<(&mut InputBuffer, &PlayerSlot), With<Player>>,
+    camera_query: Query<(&Transform, &Projection, &ColorGrading), With<MainCamera>>,
+) {
+    let Some((mut buffer, _)) = player_query.iter_mut().find(|(_, slot)| slot.is_primary()) else {
+        return;
+    };
+    if !buffer.consume(PlayerAction::PhotoMode) {
+        return;
+    }
+    let Some(camera_id) = camera_data.camera_id else {
+        return;
+    };
+    let Ok((transform, projection, grading)) = camera_query.get(camera_id) else {
+        return;
+    };
+    let Projection::Perspective(perspective) = projection else {
+        return;
+    };
+
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+    commands.entity(camera_id).insert(PhotoCamera {
+        yaw,
+        pitch,
+        roll,
+        fov: perspective.fov.to_degrees(),
+        filter: PhotoFilter::None,
+        anchor: player_data.player_position,
+        restore_fov: perspective.fov,
+        restore_grading: *grading,
+        restore_texel_snapping: pixel_config.texel_snapping,
+    });
+
+    context.push(InputContext::PhotoMode);
+    // A still camera doesn't need snapping, and leaving the canvas unshifted keeps the saved
+    // photo lined up with its texels
+    pixel_config.texel_snapping = false;
+}
+
+fn fly_photo_camera(
+    real_time: Res<Time<Real>>,
+    settings: Res<PhotoModeSettings>,
+    control_settings: Res<CameraControlSettings>,
+    spatial_query: SpatialQuery,
+    action_query: Query<&ActionState<PhotoAction>, With<PhotoInputListener>>,
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut Projection,
+        &mut ColorGrading,
+        &mut PhotoCamera,
+    )>,
+) {
+    let Ok(action) = action_query.get_single() else {
+        return;
+    };
+    let delta_seconds = real_time.delta_seconds();
+
+    for (mut transform, mut projection, mut grading, mut photo) in &mut camera_query {
+        let axis = |look| {
+            action
+                .axis_pair(&look)
+                .map_or(Vec2::ZERO, |axis_pair| axis_pair.xy())
+        };
+        let delta = control_settings.orbit_delta(
+            axis(PhotoAction::Look),
+            axis(PhotoAction::LookMouse),
+            delta_seconds,
+        );
+        if delta != Vec2::ZERO {
+            photo.yaw += delta.x.to_radians();
+            // Raising an orbiting camera tilts it down, so looking runs the other way
+            photo.pitch = (photo.pitch - delta.y.to_radians()).clamp(-1.5, 1.5);
+        }
+
+        let mut zoom = 0.0;
+        if action.pressed(&PhotoAction::ZoomIn) {
+            zoom -= 1.0;
+        }
+        if action.pressed(&PhotoAction::ZoomOut) {
+            zoom += 1.0;
+        }
+        photo.fov = (photo.fov + zoom * settings.zoom_speed * delta_seconds)
+            .clamp(settings.fov_range.x, settings.fov_range.y);
+
+        let mut roll = 0.0;
+        if action.pressed(&PhotoAction::RollLeft) {
+            roll += 1.0;
+        }
+        if action.pressed(&PhotoAction::RollRight) {
+            roll -= 1.0;
+        }
+        photo.roll += (roll * settings.roll_speed * delta_seconds).to_radians();
+
+        if action.just_pressed(&PhotoAction::Filter) {
+            photo.filter = photo.filter.next();
+        }
+
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, photo.yaw, photo.pitch, photo.roll);
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = photo.fov.to_radians();
+        }
+        *grading = photo.filter.grade(photo.restore_grading);
+
+        let input = action
+            .axis_pair(&PhotoAction::Move)
+            .map_or(Vec2::ZERO, |axis_pair| axis_pair.xy());
+        let mut rise = 0.0;
+        if action.pressed(&PhotoAction::Rise) {
+            rise += 1.0;
+        }
+        if action.pressed(&PhotoAction::Sink) {
+            rise -= 1.0;
+        }
+        let motion =
+            (*transform.right() * input.x + *transform.forward() * input.y + Vec3::Y * rise)
+                .clamp_length_max(1.0)
+                * settings.move_speed
+                * delta_seconds;
+
+        // Fly up to whatever is in the way rather than through it
+        if let Ok(direction) = Direction3d::new(motion) {
+            let distance = motion.length();
+            let allowed = spatial_query
+                .cast_shape(
+                    &Collider::sphere(settings.probe_radius),
+                    transform.translation,
+                    Quat::default(),
+                    direction,
+                    distance,
+                    true,
+                    SpatialQueryFilter::from_mask(CollisionLayer::camera_blocking_mask()),
+                )
+                .map_or(distance, |hit| hit.time_of_impact);
+            transform.translation += *direction * allowed;
+        }
+        transform.translation = photo.anchor
+            + (transform.translation - photo.anchor).clamp_length_max(settings.max_distance);
+    }
+}
+
+/// Screenshots the window and cuts the primary canvas back out of it at its own resolution
+fn capture_photo(
+    mut screenshots: ResMut<ScreenshotManager>,
+    settings: Res<PhotoModeSettings>,
+    pixel_config: Res<PixelCameraConfiguration>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    action_query: Query<&ActionState<PhotoAction>, With<PhotoInputListener>>,
+    outer_camera_query: Query<(&Camera, &PixelViewport), With<OuterCamera>>,
+) {
+    let Ok(action) = action_query.get_single() else {
+        return;
+    };
+    if !action.just_pressed(&PhotoAction::Capture) {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Some(viewport) = outer_camera_query
+        .iter()
+        .find(|(_, viewport)| viewport.0 == 0)
+        .and_then(|(camera, _)| camera.viewport.clone())
+    else {
+        return;
+    };
+    let Some(path) = photo_path() else {
+        warn!("No pictures directory found, photo was not saved");
+        return;
+    };
+
+    let canvas_size = pixel_config.canvas_size();
+    let viewport_size = viewport.physical_size.as_vec2();
+    let canvas_scale = pixel_config.canvas_scale(viewport_size.x, viewport_size.y);
+    // The canvas sits in the middle of its viewport, overhanging it when scaled to fill
+    let canvas_origin = viewport.physical_position.as_vec2()
+        + (viewport_size - canvas_size.as_vec2() * canvas_scale) * 0.5;
+    let upscale = settings.upscale.max(1);
+
+    let result = screenshots.take_screenshot(window, move |screenshot| {
+        let screenshot_size = UVec2::new(screenshot.width(), screenshot.height());
+        let pixels = resample_canvas(
+            &screenshot.data,
+            screenshot_size,
+            canvas_origin,
+            canvas_scale,
+            canvas_size,
+            upscale,
+        );
+        let size = canvas_size * upscale;
+        let photo = Image::new(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                ..default()
+            },
+            TextureDimension::D2,
+            pixels,
+            screenshot.texture_descriptor.format,
+            RenderAssetUsages::default(),
+        );
+
+        if let Some(directory) = path.parent() {
+            if let Err(error) = std::fs::create_dir_all(directory) {
+                warn!("Failed to create {}: {}", directory.display(), error);
+                return;
+            }
+        }
+        match photo.try_into_dynamic() {
+            Ok(photo) => match photo.to_rgb8().save(&path) {
+                Ok(()) => info!("Photo saved to {}", path.display()),
+                Err(error) => warn!("Failed to write {}: {}", path.display(), error),
+            },
+            Err(error) => warn!("Failed to convert photo: {}", error),
+        }
+    });
+    if result.is_err() {
+        warn!("A screenshot is already being taken, photo was not saved");
+    }
+}
+
+fn exit_photo_mode(
+    mut commands: Commands,
+    mut context: ResMut<InputContextStack>,
+    mut pixel_config: ResMut<PixelCameraConfiguration>,
+    action_query: Query<&ActionState<PhotoAction>, With<PhotoInputListener>>,
+    mut camera_query: Query<(Entity, &mut Projection, &mut ColorGrading, &PhotoCamera)>,
+) {
+    let Ok(action) = action_query.get_single() else {
+        return;
+    };
+    if !action.just_pressed(&PhotoAction::Exit) {
+        return;
+    }
+
+    // The camera's transform is left alone, MainCamera puts it back where it was next frame
+    for (entity, mut projection, mut grading, photo) in &mut camera_query {
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = photo.restore_fov;
+        }
+        *grading = photo.restore_grading;
+        pixel_config.texel_snapping = photo.restore_texel_snapping;
+        commands.entity(entity).remove::<PhotoCamera>();
+    }
+
+    context.pop_if(InputContext::PhotoMode);
+}
+
+fn photo_path() -> Option<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    dirs::picture_dir().or_else(dirs::data_dir).map(|dir| {
+        dir.join(PHOTO_DIRECTORY)
+            .join(format!("photo-{}.png", timestamp))
+    })
+}
+
+/// Takes the window pixel in the middle of each canvas texel of a screenshot, then scales the
+/// texels up by `upscale` so the photo keeps hard pixel edges. Texels that fall outside the
+/// screenshot are left black.
+fn resample_canvas(
+    screenshot: &[u8],
+    screenshot_size: UVec2,
+    canvas_origin: Vec2,
+    canvas_scale: f32,
+    canvas_size: UVec2,
+    upscale: u32,
+) -> Vec<u8> {
+    const PIXEL_SIZE: usize = 4;
+
+    let size = canvas_size * upscale;
+    let mut pixels = vec![0; size.x as usize * size.y as usize * PIXEL_SIZE];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let texel = UVec2::new(x, y) / upscale;
+            let source = (canvas_origin + (texel.as_vec2() + 0.5) * canvas_scale).floor();
+            if source.x < 0.0
+                || source.y < 0.0
+                || source.x >= screenshot_size.x as f32
+                || source.y >= screenshot_size.y as f32
+            {
+                continue;
+            }
+
+            let from =
+                (source.y as usize * screenshot_size.x as usize + source.x as usize) * PIXEL_SIZE;
+            let to = (y as usize * size.x as usize + x as usize) * PIXEL_SIZE;
+            pixels[to..to + PIXEL_SIZE].copy_from_slice(&screenshot[from..from + PIXEL_SIZE]);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn photos_sample_each_texel_once() {
+        // A 2x1 canvas drawn at 3x in the middle of a 8x3 window, red on the left and blue on
+        // the right
+        let mut screenshot = vec![0; 8 * 3 * 4];
+        for y in 0..3 {
+            for x in 1..4 {
+                screenshot[(y * 8 + x) * 4] = 255;
+            }
+            for x in 4..7 {
+                screenshot[(y * 8 + x) * 4 + 2] = 255;
+            }
+        }
+
+        let photo = resample_canvas(
+            &screenshot,
+            UVec2::new(8, 3),
+            Vec2::new(1.0, 0.0),
+            3.0,
+            UVec2::new(2, 1),
+            2,
+        );
+
+        assert_eq!(photo.len(), 4 * 2 * 4);
+        for row in photo.chunks(4 * 4) {
+            assert_eq!(&row[0..4], &[255, 0, 0, 0]);
+            assert_eq!(&row[4..8], &[255, 0, 0, 0]);
+            assert_eq!(&row[8..12], &[0, 0, 255, 0]);
+            assert_eq!(&row[12..16], &[0, 0, 255, 0]);
+        }
+    }
+}
@@ -0,0 +1,531 @@
+use super::CameraData;
+
+use bevy::prelude::*;
+use bevy::{
+    render::camera::Viewport,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    window::{PrimaryWindow, WindowResized},
+};
+
+use std::marker::PhantomData;
+
+pub const RES_WIDTH: u32 = 854;
+pub const RES_HEIGHT: u32 = 480;
+
+/// More viewports than this won't fit on a screen anyway, and each one uses up a render layer
+const MAX_VIEWPORTS: usize = 4;
+/// Distance in front of the camera where texel snapping lines up exactly, about where the player
+/// usually stands
+const DEFAULT_SNAP_DEPTH: f32 = 12.0;
+
+/// Snaps the pixel camera to the texel grid, anything that moves the primary camera should run
+/// before this
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct TexelSnapSet;
+
+pub struct PixelCameraPlugin<P, S, C>
+where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    _primary: PhantomData<P>,
+    _secondary: PhantomData<S>,
+    _canvas: PhantomData<C>,
+    resolution_width: u32,
+    resolution_height: u32,
+}
+
+impl<P, S, C> PixelCameraPlugin<P, S, C>
+where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    pub fn new(resolution_width: u32, resolution_height: u32) -> PixelCameraPlugin<P, S, C>
+    where
+        P: Component,
+        S: Component,
+    {
+        PixelCameraPlugin {
+            _primary: PhantomData,
+            _secondary: PhantomData,
+            _canvas: PhantomData,
+            resolution_width,
+            resolution_height,
+        }
+    }
+}
+
+/// How the canvas is stretched to fill the window
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CanvasScaling {
+    /// Every canvas pixel covers the same whole number of window pixels
+    #[default]
+    Integer,
+    /// As large as fits in the window, pixels may end up uneven sizes
+    Fit,
+    /// Covers the whole window, cropping whatever doesn't fit
+    Fill,
+}
+
+/// How split-screen viewports are arranged in the window
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SplitLayout {
+    /// Side by side in a row
+    #[default]
+    Horizontal,
+    /// Stacked in a column
+    Vertical,
+    /// As close to square as possible, filling each row before starting the next
+    Grid,
+}
+
+/// Which split-screen viewport a primary camera, canvas or outer camera belongs to
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+#[reflect(Component)]
+pub struct PixelViewport(pub usize);
+
+/// Settings for the pixel canvas, changing any of them applies right away
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PixelCameraConfiguration {
+    /// Resolution of the whole window's worth of canvas, split-screen viewports get a share of it
+    pub resolution_width: u32,
+    pub resolution_height: u32,
+    pub scaling: CanvasScaling,
+    /// How many split-screen viewports to render, each with its own camera and canvas
+    pub viewports: usize,
+    pub split_layout: SplitLayout,
+    /// Black bars around the canvas when it doesn't cover the window
+    pub letterbox: bool,
+    /// Lock the primary camera to whole texels and shift the canvas by the remainder instead, so
+    /// static geometry doesn't shimmer while the camera moves
+    pub texel_snapping: bool,
+    pub snap_depth: f32,
+}
+
+impl Default for PixelCameraConfiguration {
+    fn default() -> Self {
+        PixelCameraConfiguration {
+            resolution_width: RES_WIDTH,
+            resolution_height: RES_HEIGHT,
+            scaling: CanvasScaling::default(),
+            viewports: 1,
+            split_layout: SplitLayout::default(),
+            letterbox: true,
+            texel_snapping: true,
+            snap_depth: DEFAULT_SNAP_DEPTH,
+        }
+    }
+}
+
+impl<P, S, C> Plugin for PixelCameraPlugin<P, S, C>
+where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PixelCameraConfiguration {
+            resolution_width: self.resolution_width,
+            resolution_height: self.resolution_height,
+            ..default()
+        })
+        .add_systems(Startup, spawn_pixel_camera::<P, S, C>)
+        .register_type::<PixelCameraConfiguration>()
+        .register_type::<PixelViewport>()
+        .add_systems(
+            Update,
+            (
+                sync_pixel_viewports::<P, S, C>,
+                fit_pixel_canvas::<S, C>.after(sync_pixel_viewports::<P, S, C>),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            snap_to_texels::<P, C>
+                .in_set(TexelSnapSet)
+                .before(bevy::transform::TransformSystem::TransformPropagate),
+        );
+    }
+}
+// New generic versions of the systems for the pixel camera plugin
+pub fn spawn_pixel_camera<P, S, C>(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    pixel_camera_configuration: Res<PixelCameraConfiguration>,
+) where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    for index in 0..pixel_camera_configuration.viewport_count() {
+        let camera_id = spawn_viewport::<P, S, C>(
+            &mut commands,
+            &mut images,
+            &pixel_camera_configuration,
+            index,
+        );
+        if index == 0 {
+            commands.insert_resource(CameraData {
+                camera_id: Some(camera_id),
+                ..default()
+            });
+        }
+    }
+}
+
+/// Spawns the primary camera, canvas and outer camera for one viewport, returning the primary
+/// camera. The outer camera's scale and viewport are left to [`fit_pixel_canvas`].
+fn spawn_viewport<P, S, C>(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    pixel_camera_configuration: &PixelCameraConfiguration,
+    index: usize,
+) -> Entity
+where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    let size = pixel_camera_configuration.render_size();
+    let canvas_size = Extent3d {
+        width: size.x,
+        height: size.y,
+        ..default()
+    };
+
+    let mut canvas = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size: canvas_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+
+    canvas.resize(canvas_size);
+
+    let image_handle = images.add(canvas);
+    let viewport = PixelViewport(index);
+    // Each viewport gets its own layer so the outer cameras only see their own canvas
+    let high_res_layer = RenderLayers::layer(1 + index as u8);
+
+    // The Camera that renders our pixelated view to the canvas
+    let main_camera_id = commands
+        .spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    order: -1,
+                    target: RenderTarget::Image(image_handle.clone()),
+                    ..default()
+                },
+                ..default()
+            },
+            P::default(),
+            viewport,
+        ))
+        .id();
+    commands.entity(main_camera_id).insert(CameraData {
+        camera_id: Some(main_camera_id),
+        ..default()
+    });
+
+    // Canvas that the main camera is rendered to
+    commands.spawn((
+        SpriteBundle {
+            texture: image_handle,
+            ..default()
+        },
+        C::default(),
+        viewport,
+        high_res_layer,
+    ));
+
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: index as isize,
+                // Clearing the window wipes it all regardless of viewport, so only the first outer
+                // camera clears, which is also what colours the letterbox bars
+                clear_color: if index == 0 {
+                    ClearColorConfig::Custom(Color::BLACK)
+                } else {
+                    ClearColorConfig::None
+                },
+                ..default()
+            },
+            projection: OrthographicProjection {
+                near: -1000.0,
+                ..default()
+            },
+            ..default()
+        },
+        S::default(),
+        viewport,
+        high_res_layer,
+    ));
+
+    main_camera_id
+}
+
+/// Spawns or despawns viewports to match [`PixelCameraConfiguration::viewports`]
+pub fn sync_pixel_viewports<P, S, C>(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    pixel_config: Res<PixelCameraConfiguration>,
+    viewport_query: Query<(Entity, &PixelViewport)>,
+    camera_query: Query<&PixelViewport, With<P>>,
+) where
+    P: Component + Default,
+    S: Component + Default,
+    C: Component + Default,
+{
+    if !pixel_config.is_changed() {
+        return;
+    }
+
+    let count = pixel_config.viewport_count();
+    for (entity, viewport) in &viewport_query {
+        if viewport.0 >= count {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for index in 0..count {
+        if !camera_query.iter().any(|viewport| viewport.0 == index) {
+            spawn_viewport::<P, S, C>(&mut commands, &mut images, &pixel_config, index);
+        }
+    }
+}
+
+impl PixelCameraConfiguration {
+    pub fn viewport_count(&self) -> usize {
+        self.viewports.clamp(1, MAX_VIEWPORTS)
+    }
+
+    /// Columns and rows the viewports are laid out in
+    pub fn grid(&self) -> UVec2 {
+        let count = self.viewport_count() as u32;
+        match self.split_layout {
+            SplitLayout::Horizontal => UVec2::new(count, 1),
+            SplitLayout::Vertical => UVec2::new(1, count),
+            SplitLayout::Grid => {
+                let columns = (count as f32).sqrt().ceil() as u32;
+                UVec2::new(columns, count.div_ceil(columns))
+            }
+        }
+    }
+
+    /// Resolution of each viewport's canvas, split so texels stay the same size as with one view
+    pub fn canvas_size(&self) -> UVec2 {
+        (UVec2::new(self.resolution_width, self.resolution_height) / self.grid()).max(UVec2::ONE)
+    }
+
+    /// Resolution the primary camera actually renders at, the canvas plus a texel of margin on
+    /// every side. [`snap_to_texels`] shifts the canvas by up to half a texel, and the margin is
+    /// what slides into view instead of the clear colour.
+    pub fn render_size(&self) -> UVec2 {
+        self.canvas_size() + UVec2::splat(2)
+    }
+
+    /// The part of a window of the given physical size that viewport `index` is drawn in
+    pub fn pane(&self, index: usize, window_size: Vec2) -> Rect {
+        let grid = self.grid();
+        let index = index as u32;
+        let cell = (window_size / grid.as_vec2()).floor();
+        let min = UVec2::new(index % grid.x, index / grid.x).as_vec2() * cell;
+        Rect::from_corners(min, min + cell)
+    }
+
+    /// How many window pixels one canvas pixel covers in a pane of the given physical size
+    pub fn canvas_scale(&self, pane_width: f32, pane_height: f32) -> f32 {
+        let canvas_size = self.canvas_size().as_vec2();
+        let h_scale = pane_width / canvas_size.x;
+        let v_scale = pane_height / canvas_size.y;
+        match self.scaling {
+            CanvasScaling::Integer => h_scale.min(v_scale).floor().max(1.0),
+            CanvasScaling::Fit => h_scale.min(v_scale),
+            CanvasScaling::Fill => h_scale.max(v_scale),
+        }
+    }
+}
+
+/// Resizes the canvases and refits the outer cameras to their panes whenever the window or the
+/// [`PixelCameraConfiguration`] changes
+pub fn fit_pixel_canvas<S: Component, C: Component>(
+    mut resize_events: EventReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    pixel_config: Res<PixelCameraConfiguration>,
+    mut images: ResMut<Assets<Image>>,
+    canvas_query: Query<&Handle<Image>, With<C>>,
+    mut outer_camera_query: Query<
+        (&mut OrthographicProjection, &mut Camera, &PixelViewport),
+        With<S>,
+    >,
+) {
+    let resized = resize_events.read().count() > 0;
+    if !resized && !pixel_config.is_changed() {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let size = pixel_config.canvas_size();
+    if pixel_config.is_changed() {
+        let render_size = pixel_config.render_size();
+        let canvas_size = Extent3d {
+            width: render_size.x,
+            height: render_size.y,
+            ..default()
+        };
+        for handle in &canvas_query {
+            if let Some(canvas) = images.get_mut(handle) {
+                if canvas.texture_descriptor.size != canvas_size {
+                    canvas.resize(canvas_size);
+                }
+            }
+        }
+    }
+
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+
+    for (mut projection, mut camera, viewport) in &mut outer_camera_query {
+        let pane = pixel_config.pane(viewport.0, window_size);
+        let canvas_scale = pixel_config.canvas_scale(pane.width(), pane.height());
+        projection.scale = window.scale_factor() / canvas_scale;
+
+        // Everything outside the viewports keeps the first outer camera's black clear colour
+        let (position, size) =
+            if pixel_config.letterbox && pixel_config.scaling != CanvasScaling::Fill {
+                let size = (size.as_vec2() * canvas_scale).min(pane.size()).floor();
+                (pane.min + ((pane.size() - size) * 0.5).floor(), size)
+            } else {
+                (pane.min, pane.size())
+            };
+        camera.viewport = Some(Viewport {
+            physical_position: position.as_uvec2(),
+            physical_size: size.as_uvec2().max(UVec2::ONE),
+            ..default()
+        });
+    }
+}
+
+/// Moves the primary camera onto the texel grid in view space and offsets the canvas by the
+/// leftover fraction of a texel, keeping motion smooth without the pixels crawling
+pub fn snap_to_texels<P: Component, C: Component>(
+    pixel_config: Res<PixelCameraConfiguration>,
+    mut camera_query: Query<(&mut Transform, &Projection, &PixelViewport), With<P>>,
+    mut canvas_query: Query<(&mut Transform, &PixelViewport, &C), Without<P>>,
+) {
+    let texel_rows = pixel_config.render_size().y as f32;
+
+    for (mut transform, projection, viewport) in &mut camera_query {
+        let Some((mut canvas_transform, _, _)) = canvas_query
+            .iter_mut()
+            .find(|(_, canvas_viewport, _)| *canvas_viewport == viewport)
+        else {
+            continue;
+        };
+
+        if !pixel_config.texel_snapping {
+            canvas_transform.translation.x = 0.0;
+            canvas_transform.translation.y = 0.0;
+            continue;
+        }
+
+        let view_height = match projection {
+            Projection::Perspective(perspective) => {
+                2.0 * pixel_config.snap_depth * (perspective.fov * 0.5).tan()
+            }
+            Projection::Orthographic(orthographic) => orthographic.area.height(),
+        };
+        let texel = view_height / texel_rows;
+        if texel <= 0.0 {
+            continue;
+        }
+
+        let view_position = transform.rotation.inverse() * transform.translation;
+        let snapped = Vec3::new(
+            (view_position.x / texel).round() * texel,
+            (view_position.y / texel).round() * texel,
+            view_position.z,
+        );
+        let leftover = (view_position - snapped) / texel;
+
+        transform.translation = transform.rotation * snapped;
+        canvas_transform.translation.x = -leftover.x;
+        canvas_transform.translation.y = -leftover.y;
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Canvas;
+
+#[derive(Component, Default)]
+pub struct OuterCamera;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canvas_scaling_modes() {
+        let mut config = PixelCameraConfiguration::default();
+
+        assert_eq!(config.canvas_scale(1920.0, 1080.0), 2.0);
+        assert_eq!(config.canvas_scale(640.0, 360.0), 1.0);
+
+        config.scaling = CanvasScaling::Fit;
+        assert_eq!(config.canvas_scale(1708.0, 1200.0), 2.0);
+
+        config.scaling = CanvasScaling::Fill;
+        assert_eq!(config.canvas_scale(1708.0, 1200.0), 2.5);
+    }
+
+    #[test]
+    fn split_screen_layouts() {
+        let mut config = PixelCameraConfiguration {
+            viewports: 2,
+            ..default()
+        };
+        let window = Vec2::new(1920.0, 1080.0);
+
+        assert_eq!(config.canvas_size(), UVec2::new(427, 480));
+        assert_eq!(config.render_size(), UVec2::new(429, 482));
+        assert_eq!(
+            config.pane(1, window),
+            Rect::new(960.0, 0.0, 1920.0, 1080.0)
+        );
+
+        config.split_layout = SplitLayout::Vertical;
+        assert_eq!(config.canvas_size(), UVec2::new(854, 240));
+        assert_eq!(
+            config.pane(1, window),
+            Rect::new(0.0, 540.0, 1920.0, 1080.0)
+        );
+
+        config.viewports = 3;
+        config.split_layout = SplitLayout::Grid;
+        assert_eq!(config.grid(), UVec2::new(2, 2));
+        assert_eq!(config.pane(2, window), Rect::new(0.0, 540.0, 960.0, 1080.0));
+    }
+}
@@ -0,0 +1,211 @@
+use super::MainCamera;
+use crate::player::PlayerState;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+const PROFILE_PATH: &str = "profiles/player.camera.ron";
+
+/// How the camera frames its target, tuned per [`PlayerState`] and speed in a `.camera.ron` file.
+/// Builds with the `hot-reload` feature (part of `debug`) pick up edits to the file while running.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct CameraProfile {
+    /// Used for any state without its own entry
+    pub default: Vec<CameraKey>,
+    #[serde(default)]
+    pub states: HashMap<PlayerState, Vec<CameraKey>>,
+    /// How quickly the camera settles into new settings, so changing state doesn't snap
+    #[serde(default = "default_smoothing")]
+    pub smoothing: f32,
+}
+
+/// Camera settings at one speed, as a share of the target's top speed from 0.0 to 1.0. Speeds
+/// between two keys blend between them, and keys can be listed in any order.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct CameraKey {
+    pub speed: f32,
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    pub distance: f32,
+    pub height: f32,
+    pub easing: f32,
+}
+
+fn default_smoothing() -> f32 {
+    3.0
+}
+
+impl Default for CameraProfile {
+    fn default() -> Self {
+        CameraProfile {
+            default: vec![CameraKey {
+                speed: 0.0,
+                fov: 45.0,
+                distance: 10.0,
+                height: 6.5,
+                easing: 2.0,
+            }],
+            states: HashMap::default(),
+            smoothing: default_smoothing(),
+        }
+    }
+}
+
+impl CameraProfile {
+    /// Orders every list of keys by speed, which [`CameraProfile::sample`] relies on. The loader
+    /// does this once so sampling each frame doesn't have to.
+    pub fn sort_keys(&mut self) {
+        for keys in std::iter::once(&mut self.default).chain(self.states.values_mut()) {
+            keys.sort_by(|a, b| a.speed.total_cmp(&b.speed));
+        }
+    }
+
+    /// The settings for `state` at `speed`, falling back to the default keys for states the
+    /// profile doesn't mention
+    pub fn sample(&self, state: Option<PlayerState>, speed: f32) -> Option<CameraKey> {
+        let keys = state
+            .and_then(|state| self.states.get(&state))
+            .filter(|keys| !keys.is_empty())
+            .unwrap_or(&self.default);
+        let speed = speed.clamp(0.0, 1.0);
+
+        let above = keys.iter().position(|key| key.speed >= speed);
+        match above {
+            Some(0) => keys.first().copied(),
+            Some(index) => {
+                let (low, high) = (keys[index - 1], keys[index]);
+                let t = (speed - low.speed) / (high.speed - low.speed).max(f32::EPSILON);
+                Some(CameraKey {
+                    speed,
+                    fov: low.fov.lerp(high.fov, t),
+                    distance: low.distance.lerp(high.distance, t),
+                    height: low.height.lerp(high.height, t),
+                    easing: low.easing.lerp(high.easing, t),
+                })
+            }
+            None => keys.last().copied(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CameraProfileLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for CameraProfileLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CameraProfileLoaderError::Io(error) => write!(f, "Could not read profile: {}", error),
+            CameraProfileLoaderError::Ron(error) => write!(f, "Invalid profile: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CameraProfileLoaderError {}
+
+impl From<std::io::Error> for CameraProfileLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        CameraProfileLoaderError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for CameraProfileLoaderError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        CameraProfileLoaderError::Ron(error)
+    }
+}
+
+#[derive(Default)]
+pub struct CameraProfileLoader;
+
+impl AssetLoader for CameraProfileLoader {
+    type Asset = CameraProfile;
+    type Settings = ();
+    type Error = CameraProfileLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let mut profile: CameraProfile = ron::de::from_bytes(&bytes)?;
+            profile.sort_keys();
+            Ok(profile)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["camera.ron"]
+    }
+}
+
+/// The profile the [`MainCamera`]s follow, until it has loaded they use [`CameraProfile::default`]
+#[derive(Resource)]
+pub struct ActiveCameraProfile(pub Handle<CameraProfile>);
+
+pub(super) fn load_camera_profile(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ActiveCameraProfile(asset_server.load(PROFILE_PATH)));
+}
+
+pub(super) fn apply_camera_profile(
+    time: Res<Time>,
+    active_profile: Option<Res<ActiveCameraProfile>>,
+    profiles: Res<Assets<CameraProfile>>,
+    mut camera_query: Query<(&mut MainCamera, &mut Projection)>,
+) {
+    let fallback = CameraProfile::default();
+    let profile = active_profile
+        .and_then(|active| profiles.get(&active.0))
+        .unwrap_or(&fallback);
+
+    for (mut camera, mut projection) in &mut camera_query {
+        let Some(key) = profile.sample(camera.target.state, camera.target.speed_fraction) else {
+            continue;
+        };
+        let t = (time.delta_seconds() * profile.smoothing).min(1.0);
+
+        camera.offset = camera
+            .offset
+            .lerp(Vec3::new(0.0, key.height, key.distance), t);
+        camera.easing = camera.easing.lerp(key.easing, t);
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = perspective.fov.lerp(key.fov.to_radians(), t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_blend_between_speed_keys() {
+        let key = |speed: f32, fov: f32| CameraKey {
+            speed,
+            fov,
+            distance: 10.0,
+            height: 6.0,
+            easing: 2.0,
+        };
+        let mut profile = CameraProfile::default();
+        profile
+            .states
+            .insert(PlayerState::Running, vec![key(1.0, 60.0), key(0.5, 40.0)]);
+        profile.sort_keys();
+
+        let sample = |state, speed| profile.sample(state, speed).unwrap().fov;
+        assert_eq!(sample(Some(PlayerState::Running), 0.0), 40.0);
+        assert_eq!(sample(Some(PlayerState::Running), 0.75), 50.0);
+        assert_eq!(sample(Some(PlayerState::Running), 2.0), 60.0);
+        assert_eq!(sample(Some(PlayerState::Idle), 0.75), 45.0);
+        assert_eq!(sample(None, 0.0), 45.0);
+    }
+}
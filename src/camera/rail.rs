@@ -0,0 +1,296 @@
+use super::pixel::PixelViewport;
+use super::MainCamera;
+use crate::input::context::{InputContext, InputContextStack};
+use crate::input::slots::PlayerSlot;
+use crate::physics::collision::CollisionLayer;
+use crate::player::Player;
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::*;
+
+/// Samples per spline segment used when searching for the point nearest the player
+const NEAREST_SAMPLES: usize = 16;
+/// Seconds the camera's look direction takes to settle after getting on or off a rail
+const RAIL_BLEND_TIME: f32 = 0.75;
+
+/// A spline path for the camera, authored as an empty whose child empties carry [`RailPoint`]s.
+/// Parent it to a [`RailTrigger`] to have the camera get on it.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct CameraRail {
+    /// Keep looking at the player, otherwise the camera faces the way the rail points are facing
+    pub look_at_player: bool,
+}
+
+/// A control point on the parent [`CameraRail`], the spline runs through them in `index` order
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct RailPoint {
+    pub index: u32,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Default)]
+pub enum RailDrive {
+    /// The camera stays at the point on the rail nearest the player
+    #[default]
+    Player,
+    /// The camera runs along the rail over `duration` seconds, as a cutscene shot
+    Timed { duration: f32 },
+}
+
+/// A sensor volume that puts a player's camera on the [`CameraRail`] parented to it in Blender when
+/// they walk in. Player driven rails are left again when they walk out, timed rails always run to
+/// the end.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct RailTrigger {
+    pub drive: RailDrive,
+}
+
+/// Puts the camera of a split-screen viewport on a rail, or takes it off again. Timed rails push
+/// [`InputContext::Cutscene`] until they finish.
+#[derive(Event, Clone, Copy, Debug)]
+pub enum CameraRailEvent {
+    Start {
+        viewport: usize,
+        rail: Entity,
+        drive: RailDrive,
+    },
+    Stop {
+        viewport: usize,
+    },
+}
+
+impl CameraRailEvent {
+    pub fn viewport(&self) -> usize {
+        match self {
+            CameraRailEvent::Start { viewport, .. } | CameraRailEvent::Stop { viewport } => {
+                *viewport
+            }
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct ActiveRail {
+    rail: Entity,
+    drive: RailDrive,
+    progress: f32,
+}
+
+/// A point along a Catmull-Rom spline through `points`, `t` runs from 0.0 at the first point to
+/// 1.0 at the last
+pub fn sample_spline(points: &[(Vec3, Quat)], t: f32) -> (Vec3, Quat) {
+    match points.len() {
+        0 => return (Vec3::ZERO, Quat::IDENTITY),
+        1 => return points[0],
+        _ => (),
+    }
+
+    let segments = points.len() - 1;
+    let scaled = t.clamp(0.0, 1.0) * segments as f32;
+    let segment = (scaled.floor() as usize).min(segments - 1);
+    let local = scaled - segment as f32;
+
+    let point = |index: isize| points[index.clamp(0, segments as isize) as usize];
+    let (p0, _) = point(segment as isize - 1);
+    let (p1, r1) = point(segment as isize);
+    let (p2, r2) = point(segment as isize + 1);
+    let (p3, _) = point(segment as isize + 2);
+
+    let local2 = local * local;
+    let local3 = local2 * local;
+    let position = 0.5
+        * ((2.0 * p1)
+            + (p2 - p0) * local
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * local2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * local3);
+
+    (position, r1.slerp(r2, local))
+}
+
+fn nearest_progress(points: &[(Vec3, Quat)], target: Vec3) -> f32 {
+    let samples = (points.len().saturating_sub(1) * NEAREST_SAMPLES).max(1);
+    (0..=samples)
+        .map(|sample| sample as f32 / samples as f32)
+        .min_by(|a, b| {
+            let a = sample_spline(points, *a).0.distance_squared(target);
+            let b = sample_spline(points, *b).0.distance_squared(target);
+            a.total_cmp(&b)
+        })
+        .unwrap_or(0.0)
+}
+
+pub(super) fn make_rail_triggers_sensors(
+    mut commands: Commands,
+    trigger_query: Query<Entity, Added<RailTrigger>>,
+) {
+    for entity in &trigger_query {
+        commands
+            .entity(entity)
+            .insert((Sensor, CollisionLayer::camera_zone_layers()));
+    }
+}
+
+pub(super) fn trigger_camera_rails(
+    mut started: EventReader<CollisionStarted>,
+    mut ended: EventReader<CollisionEnded>,
+    mut rail_events: EventWriter<CameraRailEvent>,
+    player_query: Query<&PlayerSlot, With<Player>>,
+    trigger_query: Query<&RailTrigger>,
+    rail_query: Query<(), With<CameraRail>>,
+    children: Query<&Children>,
+) {
+    // The viewport of the player that touched a trigger, the trigger, and its drive
+    let triggered = |entity1: Entity, entity2: Entity| {
+        [(entity1, entity2), (entity2, entity1)]
+            .into_iter()
+            .find_map(|(player, other)| {
+                let slot = player_query.get(player).ok()?;
+                let trigger = trigger_query.get(other).ok()?;
+                Some((slot.0, other, trigger.drive))
+            })
+    };
+
+    for CollisionStarted(entity1, entity2) in started.read() {
+        let Some((viewport, trigger, drive)) = triggered(*entity1, *entity2) else {
+            continue;
+        };
+        match children
+            .iter_descendants(trigger)
+            .find(|child| rail_query.contains(*child))
+        {
+            Some(rail) => {
+                rail_events.send(CameraRailEvent::Start {
+                    viewport,
+                    rail,
+                    drive,
+                });
+            }
+            None => warn!(
+                "Rail trigger {:?} has no CameraRail parented to it",
+                trigger
+            ),
+        }
+    }
+
+    for CollisionEnded(entity1, entity2) in ended.read() {
+        if let Some((viewport, _, RailDrive::Player)) = triggered(*entity1, *entity2) {
+            rail_events.send(CameraRailEvent::Stop { viewport });
+        }
+    }
+}
+
+pub(super) fn start_camera_rails(
+    mut rail_events: EventReader<CameraRailEvent>,
+    mut context: ResMut<InputContextStack>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+) {
+    for event in rail_events.read() {
+        for (mut camera, viewport) in &mut camera_query {
+            if viewport.0 != event.viewport() {
+                continue;
+            }
+
+            if let Some(ActiveRail {
+                drive: RailDrive::Timed { .. },
+                ..
+            }) = camera.rail
+            {
+                context.pop_if(InputContext::Cutscene);
+            }
+
+            camera.rail = match *event {
+                CameraRailEvent::Start { rail, drive, .. } => {
+                    if let RailDrive::Timed { .. } = drive {
+                        context.push(InputContext::Cutscene);
+                    }
+                    Some(ActiveRail {
+                        rail,
+                        drive,
+                        progress: 0.0,
+                    })
+                }
+                CameraRailEvent::Stop { .. } => None,
+            };
+            camera.rail_blend = RAIL_BLEND_TIME;
+        }
+    }
+}
+
+pub(super) fn follow_camera_rails(
+    time: Res<Time>,
+    mut rail_events: EventWriter<CameraRailEvent>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+    rail_query: Query<(&CameraRail, &Children)>,
+    point_query: Query<(&RailPoint, &GlobalTransform)>,
+) {
+    for (mut camera, viewport) in &mut camera_query {
+        let Some(mut active) = camera.rail else {
+            continue;
+        };
+        let Ok((rail, children)) = rail_query.get(active.rail) else {
+            rail_events.send(CameraRailEvent::Stop {
+                viewport: viewport.0,
+            });
+            continue;
+        };
+
+        let mut points: Vec<(u32, Vec3, Quat)> = children
+            .iter()
+            .filter_map(|child| point_query.get(*child).ok())
+            .map(|(point, transform)| {
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                (point.index, translation, rotation)
+            })
+            .collect();
+        points.sort_by_key(|(index, _, _)| *index);
+        let points: Vec<(Vec3, Quat)> = points
+            .into_iter()
+            .map(|(_, translation, rotation)| (translation, rotation))
+            .collect();
+
+        active.progress = match active.drive {
+            RailDrive::Player => nearest_progress(&points, camera.target.position),
+            RailDrive::Timed { duration } => {
+                active.progress + time.delta_seconds() / duration.max(f32::EPSILON)
+            }
+        };
+        if let RailDrive::Timed { .. } = active.drive {
+            if active.progress >= 1.0 {
+                rail_events.send(CameraRailEvent::Stop {
+                    viewport: viewport.0,
+                });
+            }
+        }
+
+        let (position, rotation) = sample_spline(&points, active.progress);
+        camera.desired_position = position;
+        camera.focus = if rail.look_at_player {
+            camera.target.position
+        } else {
+            position + rotation * Vec3::NEG_Z * 10.0
+        };
+        camera.rail = Some(active);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spline_runs_through_its_points() {
+        let points = [
+            (Vec3::ZERO, Quat::IDENTITY),
+            (Vec3::new(4.0, 0.0, 0.0), Quat::IDENTITY),
+            (Vec3::new(4.0, 0.0, 4.0), Quat::IDENTITY),
+        ];
+
+        assert!(sample_spline(&points, 0.0).0.distance(points[0].0) < 0.001);
+        assert!(sample_spline(&points, 0.5).0.distance(points[1].0) < 0.001);
+        assert!(sample_spline(&points, 1.0).0.distance(points[2].0) < 0.001);
+
+        assert!((nearest_progress(&points, Vec3::new(4.5, 0.0, -0.5)) - 0.5).abs() < 0.05);
+    }
+}
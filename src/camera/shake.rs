@@ -0,0 +1,212 @@
+use super::pixel::PixelViewport;
+use super::MainCamera;
+use crate::input::slots::PlayerSlot;
+use crate::physics::types::LandingEvent;
+use crate::player::Player;
+
+use bevy::prelude::*;
+
+/// Landing slower than this doesn't shake the camera at all
+const LANDING_TRAUMA_THRESHOLD: f32 = 8.0;
+/// How much faster than the threshold a landing has to be to max out the trauma
+const LANDING_TRAUMA_RANGE: f32 = 25.0;
+
+/// Adds trauma to a [`MainCamera`], from 0.0 for nothing to 1.0 for the hardest shake
+#[derive(Event, Clone, Copy)]
+pub struct CameraTrauma {
+    pub trauma: f32,
+    /// The split-screen viewport whose camera shakes, `None` for every camera
+    pub viewport: Option<usize>,
+}
+
+/// Trauma based screen shake, the shake grows with the square of the trauma so small knocks stay
+/// subtle while big hits stack up
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraShake {
+    pub trauma: f32,
+    /// Trauma lost per second
+    pub decay: f32,
+    pub max_offset: Vec3,
+    /// Maximum yaw, pitch and roll in degrees
+    pub max_rotation: Vec3,
+    /// How quickly the shake moves, in noise samples per second
+    pub frequency: f32,
+    time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        CameraShake {
+            trauma: 0.0,
+            decay: 1.2,
+            max_offset: Vec3::new(0.4, 0.3, 0.2),
+            max_rotation: Vec3::new(2.0, 2.0, 4.0),
+            frequency: 18.0,
+            time: 0.0,
+        }
+    }
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    /// Lets the trauma wear off over `delta_seconds`, returns whether there's any shake left
+    fn decay_trauma(&mut self, delta_seconds: f32) -> bool {
+        self.trauma = (self.trauma - self.decay * delta_seconds).max(0.0);
+        if self.trauma <= 0.0 {
+            self.time = 0.0;
+            return false;
+        }
+        true
+    }
+}
+
+pub(super) fn add_camera_shake(
+    mut commands: Commands,
+    camera_query: Query<Entity, (With<MainCamera>, Without<CameraShake>)>,
+) {
+    for entity in &camera_query {
+        commands.entity(entity).insert(CameraShake::default());
+    }
+}
+
+pub(super) fn add_trauma(
+    mut trauma_events: EventReader<CameraTrauma>,
+    mut shake_query: Query<(&mut CameraShake, &PixelViewport)>,
+) {
+    for event in trauma_events.read() {
+        for (mut shake, viewport) in &mut shake_query {
+            if event.viewport.is_some_and(|target| target != viewport.0) {
+                continue;
+            }
+            shake.add_trauma(event.trauma);
+        }
+    }
+}
+
+pub(super) fn shake_on_landing(
+    mut landing_events: EventReader<LandingEvent>,
+    mut trauma_events: EventWriter<CameraTrauma>,
+    player_query: Query<&PlayerSlot, With<Player>>,
+) {
+    for LandingEvent(entity, vertical_velocity) in landing_events.read() {
+        // Only the camera showing the player who landed shakes
+        if let Ok(slot) = player_query.get(*entity) {
+            let trauma = (-vertical_velocity - LANDING_TRAUMA_THRESHOLD) / LANDING_TRAUMA_RANGE;
+            if trauma > 0.0 {
+                trauma_events.send(CameraTrauma {
+                    trauma: trauma.min(1.0),
+                    viewport: Some(slot.0),
+                });
+            }
+        }
+    }
+}
+
+/// Offsets the camera's [`Transform`] from where [`MainCamera`] placed it, this has to run last
+/// so the shake never feeds back into the camera's own movement
+pub(super) fn apply_camera_shake(
+    time: Res<Time>,
+    mut camera_query: Query<(&mut Transform, &mut CameraShake)>,
+) {
+    for (mut transform, mut shake) in &mut camera_query {
+        if !shake.decay_trauma(time.delta_seconds()) {
+            continue;
+        }
+        shake.time += time.delta_seconds() * shake.frequency;
+
+        let amount = shake.trauma * shake.trauma;
+        let sample = |seed: f32| noise(shake.time + seed * 71.3) * amount;
+
+        let offset = transform.rotation
+            * (Vec3::new(sample(0.0), sample(1.0), sample(2.0)) * shake.max_offset);
+        transform.translation += offset;
+        transform.rotation *= Quat::from_euler(
+            EulerRot::YXZ,
+            (sample(3.0) * shake.max_rotation.x).to_radians(),
+            (sample(4.0) * shake.max_rotation.y).to_radians(),
+            (sample(5.0) * shake.max_rotation.z).to_radians(),
+        );
+    }
+}
+
+/// Smooth 1D value noise in the range -1.0 to 1.0
+fn noise(x: f32) -> f32 {
+    let hash = |n: f32| {
+        let h = (n * 127.1).sin() * 43_758.547;
+        (h - h.floor()) * 2.0 - 1.0
+    };
+
+    let cell = x.floor();
+    let t = x - cell;
+    let smooth = t * t * (3.0 - 2.0 * t);
+    hash(cell).lerp(hash(cell + 1.0), smooth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trauma_stays_between_zero_and_one() {
+        let mut shake = CameraShake::default();
+        shake.add_trauma(0.6);
+        shake.add_trauma(0.6);
+        assert_eq!(shake.trauma, 1.0);
+
+        shake.add_trauma(-3.0);
+        assert_eq!(shake.trauma, 0.0);
+    }
+
+    #[test]
+    fn trauma_decays_to_zero() {
+        let mut shake = CameraShake {
+            trauma: 0.6,
+            decay: 1.2,
+            time: 5.0,
+            ..default()
+        };
+
+        assert!(shake.decay_trauma(0.25));
+        assert!((shake.trauma - 0.3).abs() < 1e-6);
+
+        assert!(!shake.decay_trauma(1.0));
+        assert_eq!(shake.trauma, 0.0);
+        assert_eq!(shake.time, 0.0);
+    }
+
+    #[test]
+    fn trauma_shakes_only_its_viewport() {
+        let mut app = App::new();
+        app.add_event::<CameraTrauma>()
+            .add_systems(Update, add_trauma);
+        let cameras: Vec<Entity> = (0..2)
+            .map(|index| {
+                app.world
+                    .spawn((CameraShake::default(), PixelViewport(index)))
+                    .id()
+            })
+            .collect();
+        let trauma =
+            |app: &App, camera: Entity| app.world.get::<CameraShake>(camera).unwrap().trauma;
+
+        app.world.send_event(CameraTrauma {
+            trauma: 0.5,
+            viewport: Some(1),
+        });
+        app.update();
+        assert_eq!(trauma(&app, cameras[0]), 0.0);
+        assert_eq!(trauma(&app, cameras[1]), 0.5);
+
+        app.world.send_event(CameraTrauma {
+            trauma: 0.25,
+            viewport: None,
+        });
+        app.update();
+        assert_eq!(trauma(&app, cameras[0]), 0.25);
+        assert_eq!(trauma(&app, cameras[1]), 0.75);
+    }
+}
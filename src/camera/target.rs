@@ -0,0 +1,193 @@
+use super::{pixel::PixelViewport, MainCamera};
+use crate::physics::types::MoveSpeed;
+use crate::player::{PlayerState, PlayerStateHandler};
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::*;
+
+/// How far the targets can spread from their centre before the camera starts pulling back
+const FRAMING_RADIUS: f32 = 4.0;
+/// The furthest the camera will pull back to fit a spread out group, as a multiple of its offset
+const MAX_FRAMING_ZOOM: f32 = 2.5;
+/// How quickly the zoom catches up as the group spreads out or bunches up
+const FRAMING_SMOOTHING: f32 = 2.0;
+
+/// Something the camera follows. With several targets active the camera centres on their weighted
+/// average and pulls back to keep all of them in frame.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraTarget {
+    /// How strongly this target pulls the centre of the frame toward itself, 0.0 ignores it
+    pub weight: f32,
+    /// Added to the target's position, e.g. to frame a vehicle's cab rather than its origin
+    pub offset: Vec3,
+    /// The split-screen viewport whose camera follows this, `None` for every camera
+    pub viewport: Option<usize>,
+}
+
+impl Default for CameraTarget {
+    fn default() -> Self {
+        CameraTarget {
+            weight: 1.0,
+            offset: Vec3::ZERO,
+            viewport: None,
+        }
+    }
+}
+
+impl CameraTarget {
+    /// Whether the camera in `viewport` follows this target. Targets meant for a viewport that
+    /// isn't open share the first one, so co-op players stay in frame without split screen.
+    pub fn follows_in(&self, viewport: PixelViewport, viewport_count: usize) -> bool {
+        match self.viewport {
+            None => true,
+            Some(index) if index >= viewport_count => viewport.0 == 0,
+            Some(index) => index == viewport.0,
+        }
+    }
+}
+
+/// What the camera is following this frame, combined from every active [`CameraTarget`]
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct TargetFrame {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Facing of the heaviest target
+    pub rotation: Quat,
+    /// Share of top speed, for targets that have a [`MoveSpeed`]
+    pub speed_fraction: f32,
+    /// What the heaviest target is doing, if it's a player
+    pub state: Option<PlayerState>,
+    /// How much further than usual the camera sits to fit every target in
+    pub zoom: f32,
+}
+
+impl Default for TargetFrame {
+    fn default() -> Self {
+        TargetFrame {
+            position: Vec3::ZERO,
+            velocity: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            speed_fraction: 0.0,
+            state: None,
+            zoom: 1.0,
+        }
+    }
+}
+
+/// The weighted centre of `targets` and the distance from it to the furthest one
+pub fn frame_targets(targets: &[(Vec3, f32)]) -> Option<(Vec3, f32)> {
+    let total_weight: f32 = targets.iter().map(|(_, weight)| weight).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    let center = targets
+        .iter()
+        .map(|(position, weight)| *position * *weight)
+        .sum::<Vec3>()
+        / total_weight;
+    let radius = targets
+        .iter()
+        .map(|(position, _)| position.distance(center))
+        .fold(0.0, f32::max);
+
+    Some((center, radius))
+}
+
+type TargetComponents<'a> = (
+    &'a Transform,
+    &'a CameraTarget,
+    Option<&'a LinearVelocity>,
+    Option<&'a MoveSpeed>,
+    Option<&'a PlayerStateHandler>,
+);
+
+pub(super) fn gather_camera_targets(
+    time: Res<Time>,
+    target_query: Query<TargetComponents>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+) {
+    let viewport_count = camera_query.iter().count();
+
+    for (mut camera, viewport) in &mut camera_query {
+        let targets: Vec<_> = target_query
+            .iter()
+            .filter(|(_, target, _, _, _)| {
+                target.weight > 0.0 && target.follows_in(*viewport, viewport_count)
+            })
+            .collect();
+        let positions: Vec<(Vec3, f32)> = targets
+            .iter()
+            .map(|(transform, target, _, _, _)| {
+                (transform.translation + target.offset, target.weight)
+            })
+            .collect();
+        // With nothing to follow the camera holds on wherever it was last looking
+        let Some((center, radius)) = frame_targets(&positions) else {
+            continue;
+        };
+
+        let total_weight: f32 = positions.iter().map(|(_, weight)| weight).sum();
+        let velocity = targets
+            .iter()
+            .map(|(_, target, velocity, _, _)| velocity.map_or(Vec3::ZERO, |v| v.0) * target.weight)
+            .sum::<Vec3>()
+            / total_weight;
+        let speed_fraction = targets
+            .iter()
+            .map(|(_, target, _, speed, _)| {
+                speed.map_or(0.0, |s| s.fraction_of_max()) * target.weight
+            })
+            .sum::<f32>()
+            / total_weight;
+        let heaviest = targets
+            .iter()
+            .max_by(|(_, a, _, _, _), (_, b, _, _, _)| a.weight.total_cmp(&b.weight));
+        let rotation =
+            heaviest.map_or(Quat::IDENTITY, |(transform, _, _, _, _)| transform.rotation);
+        let state = heaviest
+            .and_then(|(_, _, _, _, handler)| *handler)
+            .map(|handler| handler.current_state);
+        let zoom =
+            (1.0 + (radius - FRAMING_RADIUS).max(0.0) / FRAMING_RADIUS).min(MAX_FRAMING_ZOOM);
+
+        let frame = &mut camera.target;
+        frame.position = center;
+        frame.velocity = velocity;
+        frame.rotation = rotation;
+        frame.speed_fraction = speed_fraction;
+        frame.state = state;
+        frame.zoom = frame
+            .zoom
+            .lerp(zoom, (time.delta_seconds() * FRAMING_SMOOTHING).min(1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_targets_pull_the_frame() {
+        assert_eq!(frame_targets(&[]), None);
+        assert_eq!(frame_targets(&[(Vec3::X, 0.0)]), None);
+
+        let (center, radius) =
+            frame_targets(&[(Vec3::ZERO, 3.0), (Vec3::new(4.0, 0.0, 0.0), 1.0)]).unwrap();
+        assert_eq!(center, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(radius, 3.0);
+    }
+
+    #[test]
+    fn targets_share_the_first_viewport_without_split_screen() {
+        let target = CameraTarget {
+            viewport: Some(1),
+            ..default()
+        };
+
+        assert!(target.follows_in(PixelViewport(0), 1));
+        assert!(!target.follows_in(PixelViewport(0), 2));
+        assert!(target.follows_in(PixelViewport(1), 2));
+    }
+}
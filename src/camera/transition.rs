@@ -0,0 +1,198 @@
+use super::{CameraMode, MainCamera};
+
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// Shapes how a [`CameraTransition`] moves from its start to its end
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and settles
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress from 0.0 to 1.0 onto the curve
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Debug)]
+pub struct CameraTransition {
+    /// Seconds the blend takes
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl CameraTransition {
+    pub const fn new(duration: f32, easing: Easing) -> Self {
+        CameraTransition { duration, easing }
+    }
+}
+
+/// Blends every [`MainCamera`] from what it's showing now into a new mode or angle, fields left as
+/// `None` keep the camera's current setting
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CameraTransitionEvent {
+    pub transition: CameraTransition,
+    pub mode: Option<CameraMode>,
+    pub angle: Option<f32>,
+}
+
+/// A transition in progress, blending the camera's position and look point from where they were
+/// when it started to wherever the camera would be without it. The position orbits the look
+/// point rather than moving in a straight line, so a turn swings round the target instead of
+/// cutting across in front of it.
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct ActiveTransition {
+    transition: CameraTransition,
+    elapsed: f32,
+    pub from_position: Vec3,
+    pub from_look: Vec3,
+}
+
+impl ActiveTransition {
+    pub fn new(transition: CameraTransition, from_position: Vec3, from_look: Vec3) -> Self {
+        ActiveTransition {
+            transition,
+            elapsed: 0.0,
+            from_position,
+            from_look,
+        }
+    }
+
+    /// Eased progress from 0.0 at the start to 1.0 once finished
+    pub fn progress(&self) -> f32 {
+        self.transition
+            .easing
+            .apply(self.elapsed / self.transition.duration.max(f32::EPSILON))
+    }
+
+    /// Where the camera should be and look at on its way to `to_position` and `to_look`. The
+    /// position's angle round the look point takes the short way, and its distance and height
+    /// ease from one end to the other.
+    pub fn blend(&self, to_position: Vec3, to_look: Vec3) -> (Vec3, Vec3) {
+        let t = self.progress();
+        let look = self.from_look.lerp(to_look, t);
+
+        let from = self.from_position - self.from_look;
+        let to = to_position - to_look;
+        let from_angle = from.x.atan2(from.z);
+        let difference = (to.x.atan2(to.z) - from_angle + PI).rem_euclid(TAU) - PI;
+        let angle = from_angle + difference * t;
+        let distance = from.xz().length().lerp(to.xz().length(), t);
+        let height = from.y.lerp(to.y, t);
+
+        let offset = Vec3::new(angle.sin() * distance, height, angle.cos() * distance);
+        (look + offset, look)
+    }
+
+    pub fn tick(&mut self, delta_seconds: f32) {
+        self.elapsed += delta_seconds;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.transition.duration
+    }
+}
+
+pub(super) fn start_camera_transitions(
+    mut transition_events: EventReader<CameraTransitionEvent>,
+    mut camera_query: Query<&mut MainCamera>,
+) {
+    for event in transition_events.read() {
+        for mut camera in &mut camera_query {
+            camera.start_transition(event.transition);
+            if let Some(mode) = event.mode {
+                camera.camera_mode = mode;
+            }
+            if let Some(angle) = event.angle {
+                camera.angle = angle;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_curves_start_and_end_in_place() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+    }
+
+    #[test]
+    fn transitions_orbit_the_look_point() {
+        let mut active = ActiveTransition::new(
+            CameraTransition::new(1.0, Easing::Linear),
+            Vec3::new(0.0, 4.0, 10.0),
+            Vec3::ZERO,
+        );
+        let to_position = Vec3::new(10.0, 4.0, 0.0);
+
+        let (start, _) = active.blend(to_position, Vec3::ZERO);
+        assert!(start.distance(Vec3::new(0.0, 4.0, 10.0)) < 0.001);
+
+        // Half way round a quarter turn the camera is still at orbit distance, a straight line
+        // would have pulled it in to about 7
+        active.tick(0.5);
+        let (middle, look) = active.blend(to_position, Vec3::ZERO);
+        assert_eq!(look, Vec3::ZERO);
+        assert!((middle.xz().length() - 10.0).abs() < 0.001);
+        assert!((middle.y - 4.0).abs() < 0.001);
+        assert!((middle.x - middle.z).abs() < 0.001);
+
+        active.tick(0.5);
+        let (end, _) = active.blend(to_position, Vec3::ZERO);
+        assert!(end.distance(to_position) < 0.001);
+    }
+
+    #[test]
+    fn transition_events_reach_every_camera() {
+        let mut app = App::new();
+        app.add_event::<CameraTransitionEvent>()
+            .add_systems(Update, start_camera_transitions);
+        let camera = app.world.spawn(MainCamera::default()).id();
+
+        app.world.send_event(CameraTransitionEvent {
+            transition: CameraTransition::new(0.5, Easing::Linear),
+            mode: Some(CameraMode::Fixed),
+            angle: Some(90.0),
+        });
+        app.update();
+
+        let camera = app.world.get::<MainCamera>(camera).unwrap();
+        assert_eq!(camera.camera_mode, CameraMode::Fixed);
+        assert_eq!(camera.angle, 90.0);
+        assert!(camera.transition.is_some());
+    }
+}
@@ -0,0 +1,138 @@
+use super::pixel::PixelViewport;
+use super::transition::{CameraTransition, Easing};
+use super::{CameraMode, MainCamera};
+use crate::input::slots::PlayerSlot;
+use crate::physics::collision::CollisionLayer;
+use crate::player::Player;
+
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::*;
+
+/// A sensor volume that overrides a player's camera while they're inside it, e.g. a fixed angle
+/// inside the teahouse. Only the camera of the viewport the player is shown in is affected. Authored in Blender on a mesh with a collider; any field left as
+/// `None` keeps the player's own camera setting. When zones overlap the highest `priority` wins.
+#[derive(Component, Reflect, Clone, Default, Debug)]
+#[reflect(Component)]
+pub struct CameraZone {
+    pub priority: i32,
+    pub offset: Option<Vec3>,
+    pub angle: Option<f32>,
+    pub mode: Option<CameraMode>,
+    pub easing: Option<f32>,
+    /// Roughly how many seconds it takes to blend into the zone and back out again
+    pub blend_time: f32,
+}
+
+/// The camera settings currently in effect, part way between the player's own settings and the
+/// zone they're in
+#[derive(Reflect, Clone, Copy, Debug)]
+pub struct ZoneBlend {
+    pub offset: Vec3,
+    pub angle: f32,
+    pub easing: f32,
+}
+
+pub(super) fn make_zones_sensors(
+    mut commands: Commands,
+    zone_query: Query<Entity, Added<CameraZone>>,
+) {
+    for entity in &zone_query {
+        commands
+            .entity(entity)
+            .insert((Sensor, CollisionLayer::camera_zone_layers()));
+    }
+}
+
+pub(super) fn detect_camera_zones(
+    collisions: Res<Collisions>,
+    player_query: Query<(Entity, &PlayerSlot), With<Player>>,
+    zone_query: Query<&CameraZone>,
+    mut camera_query: Query<(&mut MainCamera, &PixelViewport)>,
+) {
+    for (mut camera, viewport) in &mut camera_query {
+        // Each camera only follows the zones its own player is standing in
+        let Some((player_entity, _)) = player_query.iter().find(|(_, slot)| slot.0 == viewport.0)
+        else {
+            continue;
+        };
+
+        let mut zones: Vec<&CameraZone> = collisions
+            .collisions_with_entity(player_entity)
+            .filter_map(|collision| {
+                let other = if collision.entity1 == player_entity {
+                    collision.entity2
+                } else {
+                    collision.entity1
+                };
+                zone_query.get(other).ok()
+            })
+            .collect();
+        // Sorting is stable, so overlapping zones of equal priority don't flicker between each
+        // other
+        zones.sort_by_key(|zone| -zone.priority);
+
+        let previous_mode = camera.zone.as_ref().and_then(|zone| zone.mode);
+        match zones.first() {
+            Some(zone) => {
+                camera.zone_blend_time = zone.blend_time;
+                camera.zone = Some((*zone).clone());
+            }
+            None => camera.zone = None,
+        }
+
+        // Switching mode moves the camera straight to a different offset, so ease over to it
+        if previous_mode != zones.first().and_then(|zone| zone.mode) {
+            let transition = CameraTransition::new(camera.zone_blend_time, Easing::EaseInOut);
+            camera.start_transition(transition);
+        }
+    }
+}
+
+pub(super) fn blend_camera_zones(time: Res<Time>, mut camera_query: Query<&mut MainCamera>) {
+    for mut camera in &mut camera_query {
+        if camera.zone.is_none() && camera.blend.is_none() {
+            continue;
+        }
+
+        let target = ZoneBlend {
+            offset: camera
+                .zone
+                .as_ref()
+                .and_then(|zone| zone.offset)
+                .unwrap_or(camera.offset),
+            angle: camera
+                .zone
+                .as_ref()
+                .and_then(|zone| zone.angle)
+                .unwrap_or(camera.angle),
+            easing: camera
+                .zone
+                .as_ref()
+                .and_then(|zone| zone.easing)
+                .unwrap_or(camera.easing),
+        };
+        let current = camera.blend.unwrap_or(ZoneBlend {
+            offset: camera.offset,
+            angle: camera.angle,
+            easing: camera.easing,
+        });
+
+        let t = (time.delta_seconds() / camera.zone_blend_time.max(0.01)).min(1.0);
+        let angle_difference = (target.angle - current.angle + 180.0).rem_euclid(360.0) - 180.0;
+        let blended = ZoneBlend {
+            offset: current.offset.lerp(target.offset, t),
+            angle: current.angle + angle_difference * t,
+            easing: current.easing.lerp(target.easing, t),
+        };
+
+        // Once we've blended back out of every zone the player's own settings take over again
+        let settled = blended.offset.distance(target.offset) < 0.01
+            && angle_difference.abs() < 0.1
+            && (blended.easing - target.easing).abs() < 0.01;
+        camera.blend = if camera.zone.is_none() && settled {
+            None
+        } else {
+            Some(blended)
+        };
+    }
+}
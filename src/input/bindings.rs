@@ -0,0 +1,454 @@
+use super::slots::{InputDevice, PlayerSlot, PlayerSlots};
+use super::PlayerAction;
+use bevy::{prelude::*, utils::HashMap};
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BindingProfile::load())
+            .init_resource::<PendingRebind>()
+            .add_event::<RebindRequest>()
+            .add_systems(
+                PreUpdate,
+                (start_rebind, capture_rebind)
+                    .chain()
+                    .after(bevy::input::InputSystem),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_binding_profile.run_if(
+                        resource_changed::<BindingProfile>.or_else(resource_changed::<PlayerSlots>),
+                    ),
+                    save_binding_profile.run_if(
+                        resource_changed::<BindingProfile>
+                            .and_then(not(resource_added::<BindingProfile>)),
+                    ),
+                ),
+            );
+    }
+}
+
+const PROFILE_DIRECTORY: &str = "unnamed-rpg";
+const PROFILE_FILE: &str = "bindings.ron";
+
+/// Every rebindable key and gamepad button for [`PlayerAction`], persisted as RON in the user
+/// config directory
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BindingProfile {
+    pub keyboard: HashMap<PlayerAction, Vec<KeyCode>>,
+    pub gamepad: HashMap<PlayerAction, Vec<GamepadButtonType>>,
+    pub movement: MovementKeys,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MovementKeys {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+}
+
+impl Default for MovementKeys {
+    fn default() -> Self {
+        MovementKeys {
+            up: KeyCode::KeyW,
+            down: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+        }
+    }
+}
+
+impl Default for BindingProfile {
+    fn default() -> Self {
+        use PlayerAction::*;
+
+        let keyboard = HashMap::from_iter([
+            (Jump, vec![KeyCode::Space]),
+            (Interact, vec![KeyCode::KeyL]),
+            (Crouch, vec![KeyCode::ShiftLeft]),
+            (CamRotateLeft, vec![KeyCode::ArrowLeft]),
+            (CamRotateRight, vec![KeyCode::ArrowRight]),
+            (CamModeChangePositive, vec![KeyCode::ArrowUp]),
+            (CamModeChangeNegative, vec![KeyCode::ArrowDown]),
+            (LockOn, vec![KeyCode::KeyQ]),
+            (CycleTarget, vec![KeyCode::Tab]),
+            (PhotoMode, vec![KeyCode::KeyP]),
+        ]);
+
+        let gamepad = HashMap::from_iter([
+            (Jump, vec![GamepadButtonType::South]),
+            (Interact, vec![GamepadButtonType::West]),
+            (Crouch, vec![GamepadButtonType::LeftTrigger]),
+            (CamRotateLeft, vec![GamepadButtonType::LeftTrigger2]),
+            (CamRotateRight, vec![GamepadButtonType::RightTrigger2]),
+            (LockOn, vec![GamepadButtonType::RightThumb]),
+            (CycleTarget, vec![GamepadButtonType::RightTrigger]),
+            (PhotoMode, vec![GamepadButtonType::Select]),
+        ]);
+
+        BindingProfile {
+            keyboard,
+            gamepad,
+            movement: MovementKeys::default(),
+        }
+    }
+}
+
+impl BindingProfile {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(PROFILE_DIRECTORY).join(PROFILE_FILE))
+    }
+
+    /// Reads the saved profile, falling back to the default bindings if the file is missing or
+    /// can't be parsed. Actions missing from a saved profile keep their default bindings.
+    pub fn load() -> Self {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<BindingProfile>(&contents) {
+            Ok(mut profile) => {
+                profile.fill_missing(&Self::default());
+                profile
+            }
+            Err(error) => {
+                warn!(
+                    "Invalid binding profile at {}, using defaults: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No config directory found, binding profile was not saved");
+            return;
+        };
+
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!("Failed to serialize binding profile: {}", error);
+                return;
+            }
+        };
+
+        if let Some(directory) = path.parent() {
+            if let Err(error) = std::fs::create_dir_all(directory) {
+                warn!("Failed to create {}: {}", directory.display(), error);
+                return;
+            }
+        }
+
+        if let Err(error) = std::fs::write(&path, contents) {
+            warn!("Failed to write {}: {}", path.display(), error);
+        }
+    }
+
+    fn fill_missing(&mut self, defaults: &BindingProfile) {
+        for (action, keys) in defaults.keyboard.iter() {
+            self.keyboard.entry(*action).or_insert_with(|| keys.clone());
+        }
+        for (action, buttons) in defaults.gamepad.iter() {
+            self.gamepad
+                .entry(*action)
+                .or_insert_with(|| buttons.clone());
+        }
+    }
+
+    /// Binds `key` to `action`. If another action already used `key` it takes `action`'s old keys
+    /// instead, so a key never fires two actions and neither action is left unbound.
+    pub fn bind_key(&mut self, action: PlayerAction, key: KeyCode) {
+        let previous = self.keyboard.insert(action, vec![key]).unwrap_or_default();
+        for (other, keys) in self.keyboard.iter_mut() {
+            if *other != action && keys.contains(&key) {
+                keys.retain(|bound| *bound != key);
+                for bound in &previous {
+                    if !keys.contains(bound) {
+                        keys.push(*bound);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Binds `button` to `action`, swapping bindings with whichever action used it before
+    pub fn bind_button(&mut self, action: PlayerAction, button: GamepadButtonType) {
+        let previous = self
+            .gamepad
+            .insert(action, vec![button])
+            .unwrap_or_default();
+        for (other, buttons) in self.gamepad.iter_mut() {
+            if *other != action && buttons.contains(&button) {
+                buttons.retain(|bound| *bound != button);
+                for bound in &previous {
+                    if !buttons.contains(bound) {
+                        buttons.push(*bound);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds the bindings for a single player, only listening to the devices they own
+    pub fn input_map(&self, device: InputDevice) -> InputMap<PlayerAction> {
+        let mut input_map = InputMap::default();
+
+        if device.keyboard {
+            for (action, keys) in self.keyboard.iter() {
+                for key in keys {
+                    input_map.insert(*action, *key);
+                }
+            }
+
+            input_map.insert(
+                PlayerAction::Move,
+                VirtualDPad {
+                    up: self.movement.up.into(),
+                    down: self.movement.down.into(),
+                    left: self.movement.left.into(),
+                    right: self.movement.right.into(),
+                },
+            );
+            input_map.insert(PlayerAction::CamOrbitMouse, DualAxis::mouse_motion());
+        }
+
+        if let Some(gamepad) = device.gamepad {
+            for (action, buttons) in self.gamepad.iter() {
+                for button in buttons {
+                    input_map.insert(*action, *button);
+                }
+            }
+
+            input_map
+                .insert(PlayerAction::Move, DualAxis::left_stick())
+                .insert(PlayerAction::CamOrbit, DualAxis::right_stick())
+                .set_gamepad(gamepad);
+        }
+
+        input_map
+    }
+}
+
+/// What the next key or button press will be bound to
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebindTarget {
+    Keyboard(PlayerAction),
+    Gamepad(PlayerAction),
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+}
+
+impl RebindTarget {
+    fn wants_keyboard(&self) -> bool {
+        !matches!(self, RebindTarget::Gamepad(_))
+    }
+}
+
+/// Send this to start a "press a key to bind" prompt, the next press on the matching device is
+/// written to the [`BindingProfile`] and saved. Escape cancels a pending rebind.
+#[derive(Event, Clone, Copy)]
+pub struct RebindRequest(pub RebindTarget);
+
+#[derive(Resource, Default)]
+pub struct PendingRebind(Option<RebindTarget>);
+
+#[allow(unused)]
+impl PendingRebind {
+    pub fn get(&self) -> Option<RebindTarget> {
+        self.0
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+fn start_rebind(mut requests: EventReader<RebindRequest>, mut pending: ResMut<PendingRebind>) {
+    if let Some(request) = requests.read().last() {
+        pending.0 = Some(request.0);
+    }
+}
+
+fn capture_rebind(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    mut pending: ResMut<PendingRebind>,
+    mut profile: ResMut<BindingProfile>,
+) {
+    let Some(target) = pending.0 else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        pending.0 = None;
+        return;
+    }
+
+    if target.wants_keyboard() {
+        if let Some(key) = keys.get_just_pressed().next().copied() {
+            match target {
+                RebindTarget::Keyboard(action) => profile.bind_key(action, key),
+                RebindTarget::MoveUp => profile.movement.up = key,
+                RebindTarget::MoveDown => profile.movement.down = key,
+                RebindTarget::MoveLeft => profile.movement.left = key,
+                RebindTarget::MoveRight => profile.movement.right = key,
+                RebindTarget::Gamepad(_) => (),
+            }
+            pending.0 = None;
+        }
+    } else if let RebindTarget::Gamepad(action) = target {
+        if let Some(button) = buttons.get_just_pressed().next() {
+            profile.bind_button(action, button.button_type);
+            pending.0 = None;
+        }
+    }
+}
+
+fn save_binding_profile(profile: Res<BindingProfile>) {
+    profile.save();
+}
+
+pub(crate) fn apply_binding_profile(
+    profile: Res<BindingProfile>,
+    slots: Res<PlayerSlots>,
+    mut input_maps: Query<(&mut InputMap<PlayerAction>, &PlayerSlot)>,
+) {
+    for (mut input_map, slot) in &mut input_maps {
+        *input_map = profile.input_map(slots.device(*slot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the temp dir no other test, or test run, will be using at the same time
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("unnamed-rpg-{}-{}.ron", std::process::id(), name))
+    }
+
+    fn temp_profile(name: &str, contents: &str) -> PathBuf {
+        let path = temp_path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn unreadable_profiles_fall_back_to_defaults() {
+        let missing = temp_path("missing-bindings");
+        assert_eq!(
+            BindingProfile::load_from(&missing),
+            BindingProfile::default()
+        );
+
+        let invalid = temp_profile("invalid-bindings", "(keyboard: {Jump: [Nope]})");
+        let profile = BindingProfile::load_from(&invalid);
+        std::fs::remove_file(&invalid).unwrap();
+        assert_eq!(profile, BindingProfile::default());
+    }
+
+    #[test]
+    fn saved_profiles_keep_defaults_for_missing_actions() {
+        let path = temp_profile(
+            "partial-bindings",
+            "(keyboard: {Jump: [KeyJ]}, gamepad: {}, movement: (up: KeyI, down: KeyK, left: KeyJ, right: KeyL))",
+        );
+        let profile = BindingProfile::load_from(&path);
+        std::fs::remove_file(&path).unwrap();
+        let defaults = BindingProfile::default();
+
+        assert_eq!(profile.keyboard[&PlayerAction::Jump], vec![KeyCode::KeyJ]);
+        assert_eq!(
+            profile.keyboard[&PlayerAction::Interact],
+            defaults.keyboard[&PlayerAction::Interact]
+        );
+        assert_eq!(profile.gamepad, defaults.gamepad);
+        assert_eq!(profile.movement.up, KeyCode::KeyI);
+    }
+
+    #[test]
+    fn rebind_requests_bind_the_next_press() {
+        let mut app = App::new();
+        app.insert_resource(BindingProfile::default())
+            .init_resource::<PendingRebind>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .add_event::<RebindRequest>()
+            .add_systems(Update, (start_rebind, capture_rebind).chain());
+
+        app.world
+            .send_event(RebindRequest(RebindTarget::Keyboard(PlayerAction::Jump)));
+        app.update();
+        assert!(app.world.resource::<PendingRebind>().is_waiting());
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyL);
+        app.update();
+
+        let profile = app.world.resource::<BindingProfile>();
+        assert!(!app.world.resource::<PendingRebind>().is_waiting());
+        assert_eq!(profile.keyboard[&PlayerAction::Jump], vec![KeyCode::KeyL]);
+        // Interact swapped onto Jump's old key rather than both firing on L
+        assert_eq!(
+            profile.keyboard[&PlayerAction::Interact],
+            vec![KeyCode::Space]
+        );
+    }
+
+    #[test]
+    fn binding_a_used_input_swaps_it_with_the_other_action() {
+        let mut profile = BindingProfile::default();
+
+        profile.bind_key(PlayerAction::Crouch, KeyCode::Space);
+        assert_eq!(
+            profile.keyboard[&PlayerAction::Crouch],
+            vec![KeyCode::Space]
+        );
+        assert_eq!(
+            profile.keyboard[&PlayerAction::Jump],
+            BindingProfile::default().keyboard[&PlayerAction::Crouch]
+        );
+
+        profile.bind_button(PlayerAction::Interact, GamepadButtonType::South);
+        assert_eq!(
+            profile.gamepad[&PlayerAction::Interact],
+            vec![GamepadButtonType::South]
+        );
+        assert_eq!(
+            profile.gamepad[&PlayerAction::Jump],
+            vec![GamepadButtonType::West]
+        );
+
+        // Neither input is left firing more than one action
+        let space = profile.keyboard.values().flatten();
+        assert_eq!(space.filter(|key| **key == KeyCode::Space).count(), 1);
+        let south = profile.gamepad.values().flatten();
+        assert_eq!(
+            south
+                .filter(|button| **button == GamepadButtonType::South)
+                .count(),
+            1
+        );
+    }
+}
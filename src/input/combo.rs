@@ -0,0 +1,301 @@
+use super::{buffer_inputs, InputBuffer, PlayerAction};
+use crate::physics::types::Grounded;
+use crate::types::EngineSystemSet;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ComboEvent>()
+            .init_resource::<ComboList>()
+            .add_systems(
+                FixedUpdate,
+                detect_combos
+                    .after(buffer_inputs)
+                    .in_set(EngineSystemSet::Input),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize, Reflect)]
+pub enum ComboId {
+    LongJump,
+    Dive,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ComboPattern {
+    /// Every action pressed in order, each within `window` of the press before it. Listing the
+    /// same action twice makes a double tap.
+    Sequence {
+        actions: Vec<PlayerAction>,
+        window: Duration,
+    },
+    /// Every action pressed, in any order, within `window` of the first one
+    Simultaneous {
+        actions: Vec<PlayerAction>,
+        window: Duration,
+    },
+}
+
+impl ComboPattern {
+    /// Checks whether the newest presses in `presses` (oldest first) complete this pattern
+    pub fn matches(&self, presses: &[(PlayerAction, Duration)]) -> bool {
+        match self {
+            ComboPattern::Sequence { actions, window } => {
+                if actions.is_empty() || presses.len() < actions.len() {
+                    return false;
+                }
+                let tail = &presses[presses.len() - actions.len()..];
+
+                tail.iter()
+                    .zip(actions.iter())
+                    .all(|((pressed, _), action)| pressed == action)
+                    && tail.windows(2).all(|pair| pair[1].1 - pair[0].1 <= *window)
+            }
+            ComboPattern::Simultaneous { actions, window } => {
+                if actions.is_empty() || presses.len() < actions.len() {
+                    return false;
+                }
+                let tail = &presses[presses.len() - actions.len()..];
+
+                actions
+                    .iter()
+                    .all(|action| tail.iter().any(|(pressed, _)| pressed == action))
+                    && tail[tail.len() - 1].1 - tail[0].1 <= *window
+            }
+        }
+    }
+}
+
+/// Where the character has to be for a combo to count
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ComboCondition {
+    #[default]
+    Any,
+    Grounded,
+    Airborne,
+}
+
+impl ComboCondition {
+    pub fn allows(self, grounded: bool) -> bool {
+        match self {
+            ComboCondition::Any => true,
+            ComboCondition::Grounded => grounded,
+            ComboCondition::Airborne => !grounded,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Combo {
+    pub id: ComboId,
+    pub pattern: ComboPattern,
+    #[serde(default)]
+    pub condition: ComboCondition,
+}
+
+/// Every combo the matcher listens for, checked in order so earlier entries win when two combos
+/// complete on the same press
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct ComboList(pub Vec<Combo>);
+
+impl Default for ComboList {
+    fn default() -> Self {
+        use PlayerAction::*;
+
+        ComboList(vec![
+            Combo {
+                id: ComboId::LongJump,
+                pattern: ComboPattern::Sequence {
+                    actions: vec![Crouch, Jump],
+                    window: Duration::from_millis(250),
+                },
+                condition: ComboCondition::Grounded,
+            },
+            Combo {
+                id: ComboId::Dive,
+                pattern: ComboPattern::Sequence {
+                    actions: vec![Jump, Jump],
+                    window: Duration::from_millis(300),
+                },
+                condition: ComboCondition::Airborne,
+            },
+        ])
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ComboEvent {
+    pub entity: Entity,
+    pub combo: ComboId,
+}
+
+/// Tracks which presses in an entity's [`InputBuffer`] have already been matched, so a press is
+/// only ever part of one combo
+#[derive(Component, Default)]
+pub struct ComboMatcher {
+    last_seen: Option<Duration>,
+    last_match: Option<Duration>,
+}
+
+fn detect_combos(
+    combos: Res<ComboList>,
+    mut combo_events: EventWriter<ComboEvent>,
+    mut query: Query<(Entity, &InputBuffer, &mut ComboMatcher, Has<Grounded>)>,
+) {
+    for (entity, buffer, mut matcher, grounded) in &mut query {
+        let presses: Vec<(PlayerAction, Duration)> = buffer
+            .press_history()
+            .filter(|(_, time)| match matcher.last_match {
+                Some(last) => *time > last,
+                None => true,
+            })
+            .copied()
+            .collect();
+
+        let Some(&(_, newest)) = presses.last() else {
+            continue;
+        };
+
+        if matcher.last_seen.is_some_and(|last| newest <= last) {
+            continue;
+        }
+        matcher.last_seen = Some(newest);
+
+        if let Some(combo) = combos
+            .0
+            .iter()
+            .find(|combo| combo.condition.allows(grounded) && combo.pattern.matches(&presses))
+        {
+            matcher.last_match = Some(newest);
+            combo_events.send(ComboEvent {
+                entity,
+                combo: combo.id,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::context::InputContextStack;
+    use leafwing_input_manager::prelude::ActionState;
+    use PlayerAction::*;
+
+    fn at(action: PlayerAction, millis: u64) -> (PlayerAction, Duration) {
+        (action, Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn sequence_needs_order_and_window() {
+        let pattern = ComboPattern::Sequence {
+            actions: vec![Crouch, Jump],
+            window: Duration::from_millis(250),
+        };
+
+        assert!(pattern.matches(&[at(Interact, 0), at(Crouch, 100), at(Jump, 300)]));
+        assert!(!pattern.matches(&[at(Jump, 100), at(Crouch, 300)]));
+        assert!(!pattern.matches(&[at(Crouch, 100), at(Jump, 400)]));
+        assert!(!pattern.matches(&[at(Crouch, 100), at(Jump, 200), at(Interact, 250)]));
+    }
+
+    #[test]
+    fn double_tap() {
+        let pattern = ComboPattern::Sequence {
+            actions: vec![Jump, Jump],
+            window: Duration::from_millis(300),
+        };
+
+        assert!(pattern.matches(&[at(Jump, 0), at(Jump, 250)]));
+        assert!(!pattern.matches(&[at(Jump, 250)]));
+        assert!(!pattern.matches(&[at(Jump, 0), at(Jump, 350)]));
+    }
+
+    #[test]
+    fn simultaneous_ignores_order() {
+        let pattern = ComboPattern::Simultaneous {
+            actions: vec![Crouch, Interact],
+            window: Duration::from_millis(50),
+        };
+
+        assert!(pattern.matches(&[at(Interact, 10), at(Crouch, 40)]));
+        assert!(pattern.matches(&[at(Crouch, 10), at(Interact, 10)]));
+        assert!(!pattern.matches(&[at(Crouch, 10), at(Interact, 100)]));
+        assert!(!pattern.matches(&[at(Crouch, 10), at(Crouch, 20)]));
+    }
+
+    #[test]
+    fn conditions_check_grounded() {
+        assert!(ComboCondition::Any.allows(true) && ComboCondition::Any.allows(false));
+        assert!(ComboCondition::Grounded.allows(true));
+        assert!(!ComboCondition::Grounded.allows(false));
+        assert!(ComboCondition::Airborne.allows(false));
+        assert!(!ComboCondition::Airborne.allows(true));
+    }
+
+    #[test]
+    fn one_press_over_two_fixed_ticks_is_not_a_double_tap() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<InputContextStack>()
+            .init_resource::<ComboList>()
+            .add_event::<ComboEvent>()
+            .add_systems(Update, (buffer_inputs, detect_combos).chain());
+        // Each update is a 16ms fixed tick
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(16));
+
+        let mut action_state = ActionState::<PlayerAction>::default();
+        action_state.press(&Jump);
+        let player = app
+            .world
+            .spawn((
+                action_state,
+                InputBuffer::default(),
+                ComboMatcher::default(),
+            ))
+            .id();
+
+        // Two fixed ticks in one frame, both seeing Jump as just pressed
+        app.update();
+        app.update();
+        assert!(app.world.resource::<Events<ComboEvent>>().is_empty());
+        assert_eq!(
+            app.world
+                .get::<InputBuffer>(player)
+                .unwrap()
+                .press_history()
+                .count(),
+            1
+        );
+
+        // A real second press still dives
+        let mut action_state = app
+            .world
+            .get_mut::<ActionState<PlayerAction>>(player)
+            .unwrap();
+        action_state.release(&Jump);
+        app.update();
+        let mut action_state = app
+            .world
+            .get_mut::<ActionState<PlayerAction>>(player)
+            .unwrap();
+        action_state.press(&Jump);
+        app.update();
+        let events = app.world.resource::<Events<ComboEvent>>();
+        assert_eq!(
+            events
+                .get_reader()
+                .read(events)
+                .map(|event| event.combo)
+                .collect::<Vec<_>>(),
+            vec![ComboId::Dive]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct InputContextPlugin;
+
+impl Plugin for InputContextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<MenuAction>::default())
+            .init_resource::<InputContextStack>()
+            .add_systems(Startup, spawn_menu_listener)
+            .add_systems(
+                PreUpdate,
+                toggle_menu_actions
+                    .before(bevy::input::InputSystem)
+                    .run_if(resource_changed::<InputContextStack>),
+            );
+    }
+}
+
+/// Who currently owns the player's input, only the context on top of the [`InputContextStack`]
+/// receives it
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect)]
+pub enum InputContext {
+    Overworld,
+    Menu,
+    Dialogue,
+    Cutscene,
+    /// Gameplay is paused while the camera is flown around for a photo
+    PhotoMode,
+}
+
+impl InputContext {
+    pub fn uses_player_actions(&self) -> bool {
+        *self == InputContext::Overworld
+    }
+
+    pub fn uses_menu_actions(&self) -> bool {
+        matches!(self, InputContext::Menu | InputContext::Dialogue)
+    }
+}
+
+#[derive(
+    Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Default, Reflect, Serialize, Deserialize,
+)]
+pub enum MenuAction {
+    #[default]
+    Confirm,
+    Back,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A stack of [`InputContext`]s with [`InputContext::Overworld`] at the bottom. Opening a menu or
+/// a dialogue pushes its context and closing it pops it again, so nested menus hand input back in
+/// the right order.
+#[derive(Resource, Debug)]
+pub struct InputContextStack(Vec<InputContext>);
+
+impl Default for InputContextStack {
+    fn default() -> Self {
+        InputContextStack(vec![InputContext::Overworld])
+    }
+}
+
+#[allow(unused)]
+impl InputContextStack {
+    pub fn top(&self) -> InputContext {
+        self.0.last().copied().unwrap_or(InputContext::Overworld)
+    }
+
+    pub fn is_active(&self, context: InputContext) -> bool {
+        self.top() == context
+    }
+
+    pub fn push(&mut self, context: InputContext) {
+        self.0.push(context);
+    }
+
+    /// Removes the top context and returns it, the bottom Overworld context is never popped
+    pub fn pop(&mut self) -> Option<InputContext> {
+        if self.0.len() > 1 {
+            self.0.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Pops `context` only if it is on top, for callers that shouldn't close someone else's menu
+    pub fn pop_if(&mut self, context: InputContext) -> bool {
+        if self.0.len() > 1 && self.is_active(context) {
+            self.0.pop();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Run condition for systems that should only run while `context` owns input
+#[allow(unused)]
+pub fn in_input_context(context: InputContext) -> impl Fn(Res<InputContextStack>) -> bool {
+    move |stack: Res<InputContextStack>| stack.is_active(context)
+}
+
+/// Run condition for gameplay systems, which stop while photo mode has the game paused
+pub fn gameplay_running(stack: Res<InputContextStack>) -> bool {
+    !stack.is_active(InputContext::PhotoMode)
+}
+
+/// Menus are shared between every local player, so a single listener reads the keyboard and any
+/// gamepad
+#[derive(Component)]
+pub struct MenuInputListener;
+
+fn menu_input_map() -> InputMap<MenuAction> {
+    use MenuAction::*;
+
+    InputMap::new([
+        (Confirm, KeyCode::Enter),
+        (Confirm, KeyCode::Space),
+        (Back, KeyCode::Escape),
+        (Back, KeyCode::Backspace),
+        (Up, KeyCode::ArrowUp),
+        (Up, KeyCode::KeyW),
+        (Down, KeyCode::ArrowDown),
+        (Down, KeyCode::KeyS),
+        (Left, KeyCode::ArrowLeft),
+        (Left, KeyCode::KeyA),
+        (Right, KeyCode::ArrowRight),
+        (Right, KeyCode::KeyD),
+    ])
+    .insert_multiple([
+        (Confirm, GamepadButtonType::South),
+        (Back, GamepadButtonType::East),
+        (Up, GamepadButtonType::DPadUp),
+        (Down, GamepadButtonType::DPadDown),
+        (Left, GamepadButtonType::DPadLeft),
+        (Right, GamepadButtonType::DPadRight),
+    ])
+    .build()
+}
+
+fn spawn_menu_listener(mut commands: Commands) {
+    commands.spawn((
+        Name::from("Menu Input"),
+        MenuInputListener,
+        InputManagerBundle {
+            input_map: menu_input_map(),
+            ..default()
+        },
+    ));
+}
+
+fn toggle_menu_actions(
+    stack: Res<InputContextStack>,
+    mut toggle_actions: ResMut<ToggleActions<MenuAction>>,
+) {
+    toggle_actions.enabled = stack.top().uses_menu_actions();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overworld_is_never_popped() {
+        let mut stack = InputContextStack::default();
+        assert_eq!(stack.pop(), None);
+
+        stack.push(InputContext::Menu);
+        stack.push(InputContext::Dialogue);
+        assert!(stack.is_active(InputContext::Dialogue));
+        assert!(!stack.pop_if(InputContext::Menu));
+
+        assert_eq!(stack.pop(), Some(InputContext::Dialogue));
+        assert!(stack.pop_if(InputContext::Menu));
+        assert!(stack.is_active(InputContext::Overworld));
+        assert_eq!(stack.pop(), None);
+    }
+}
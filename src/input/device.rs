@@ -0,0 +1,261 @@
+use super::bindings::BindingProfile;
+use super::PlayerAction;
+use bevy::{input::mouse::MouseMotion, prelude::*};
+
+pub struct ActiveDevicePlugin;
+
+impl Plugin for ActiveDevicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveInputDevice>().add_systems(
+            PreUpdate,
+            detect_active_device.after(bevy::input::InputSystem),
+        );
+    }
+}
+
+/// Stick tilt needed before a gamepad counts as the active device, so a drifting stick can't
+/// steal the prompts from the keyboard
+const GAMEPAD_AXIS_THRESHOLD: f32 = 0.5;
+const MOUSE_MOTION_THRESHOLD: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GamepadFamily {
+    Xbox,
+    PlayStation,
+    Nintendo,
+    #[default]
+    Generic,
+}
+
+impl GamepadFamily {
+    /// Guesses the family from the name the OS reports for the gamepad
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let matches_any = |patterns: &[&str]| patterns.iter().any(|pattern| name.contains(pattern));
+
+        if matches_any(&["xbox", "xinput"]) {
+            GamepadFamily::Xbox
+        } else if matches_any(&[
+            "playstation",
+            "dualshock",
+            "dualsense",
+            "sony",
+            "ps4",
+            "ps5",
+        ]) {
+            GamepadFamily::PlayStation
+        } else if matches_any(&["nintendo", "switch", "joy-con", "pro controller"]) {
+            GamepadFamily::Nintendo
+        } else {
+            GamepadFamily::Generic
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            GamepadFamily::Xbox => "xbox",
+            GamepadFamily::PlayStation => "ps",
+            GamepadFamily::Nintendo => "switch",
+            GamepadFamily::Generic => "gamepad",
+        }
+    }
+
+    fn button_name(&self, button: GamepadButtonType) -> String {
+        use GamepadButtonType::*;
+
+        let name = match (self, button) {
+            (GamepadFamily::Xbox, South) => "a",
+            (GamepadFamily::Xbox, East) => "b",
+            (GamepadFamily::Xbox, West) => "x",
+            (GamepadFamily::Xbox, North) => "y",
+            (GamepadFamily::Xbox, LeftTrigger) => "lb",
+            (GamepadFamily::Xbox, RightTrigger) => "rb",
+            (GamepadFamily::Xbox, LeftTrigger2) => "lt",
+            (GamepadFamily::Xbox, RightTrigger2) => "rt",
+            (GamepadFamily::PlayStation, South) => "cross",
+            (GamepadFamily::PlayStation, East) => "circle",
+            (GamepadFamily::PlayStation, West) => "square",
+            (GamepadFamily::PlayStation, North) => "triangle",
+            (GamepadFamily::PlayStation, LeftTrigger) => "l1",
+            (GamepadFamily::PlayStation, RightTrigger) => "r1",
+            (GamepadFamily::PlayStation, LeftTrigger2) => "l2",
+            (GamepadFamily::PlayStation, RightTrigger2) => "r2",
+            // Nintendo swaps the face button labels relative to their position
+            (GamepadFamily::Nintendo, South) => "b",
+            (GamepadFamily::Nintendo, East) => "a",
+            (GamepadFamily::Nintendo, West) => "y",
+            (GamepadFamily::Nintendo, North) => "x",
+            (GamepadFamily::Nintendo, LeftTrigger) => "l",
+            (GamepadFamily::Nintendo, RightTrigger) => "r",
+            (GamepadFamily::Nintendo, LeftTrigger2) => "zl",
+            (GamepadFamily::Nintendo, RightTrigger2) => "zr",
+            _ => return format!("{}_{}", self.prefix(), snake_case(&format!("{:?}", button))),
+        };
+
+        format!("{}_{}", self.prefix(), name)
+    }
+}
+
+/// The device the player last used, so prompts can show the buttons they're actually holding
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ActiveInputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad(GamepadFamily),
+}
+
+#[allow(unused)]
+impl ActiveInputDevice {
+    /// The glyph names for every input bound to `action` on this device, e.g. `key_space` or
+    /// `ps_cross`. Move lists its up, left, down and right keys on keyboard, the sticks
+    /// and the mouse are named directly.
+    pub fn glyphs(&self, profile: &BindingProfile, action: PlayerAction) -> Vec<String> {
+        match (self, action) {
+            (ActiveInputDevice::KeyboardMouse, PlayerAction::Move) => [
+                profile.movement.up,
+                profile.movement.left,
+                profile.movement.down,
+                profile.movement.right,
+            ]
+            .iter()
+            .map(|key| key_glyph(*key))
+            .collect(),
+            (
+                ActiveInputDevice::KeyboardMouse,
+                PlayerAction::CamOrbit | PlayerAction::CamOrbitMouse,
+            ) => {
+                vec!["mouse_motion".to_string()]
+            }
+            (ActiveInputDevice::KeyboardMouse, _) => profile
+                .keyboard
+                .get(&action)
+                .map(|keys| keys.iter().map(|key| key_glyph(*key)).collect())
+                .unwrap_or_default(),
+            (ActiveInputDevice::Gamepad(family), PlayerAction::Move) => {
+                vec![format!("{}_left_stick", family.prefix())]
+            }
+            (
+                ActiveInputDevice::Gamepad(family),
+                PlayerAction::CamOrbit | PlayerAction::CamOrbitMouse,
+            ) => {
+                vec![format!("{}_right_stick", family.prefix())]
+            }
+            (ActiveInputDevice::Gamepad(family), _) => profile
+                .gamepad
+                .get(&action)
+                .map(|buttons| {
+                    buttons
+                        .iter()
+                        .map(|button| family.button_name(*button))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The first glyph bound to `action`, for prompts with room for a single button
+    pub fn glyph(&self, profile: &BindingProfile, action: PlayerAction) -> Option<String> {
+        self.glyphs(profile, action).into_iter().next()
+    }
+}
+
+fn key_glyph(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    let name = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+    format!("key_{}", snake_case(name))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, character) in name.chars().enumerate() {
+        if character.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.push(character.to_ascii_lowercase());
+    }
+    snake
+}
+
+fn detect_active_device(
+    mut active_device: ResMut<ActiveInputDevice>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    let moved_mouse = mouse_motion
+        .read()
+        .map(|motion| motion.delta.length())
+        .sum::<f32>()
+        > MOUSE_MOTION_THRESHOLD;
+
+    let used_gamepad = gamepad_buttons
+        .get_just_pressed()
+        .map(|button| button.gamepad)
+        .next()
+        .or_else(|| {
+            gamepad_axes
+                .devices()
+                .find(|axis| {
+                    gamepad_axes
+                        .get(**axis)
+                        .is_some_and(|value| value.abs() > GAMEPAD_AXIS_THRESHOLD)
+                })
+                .map(|axis| axis.gamepad)
+        });
+
+    if let Some(gamepad) = used_gamepad {
+        let family = gamepads
+            .name(gamepad)
+            .map(GamepadFamily::from_name)
+            .unwrap_or_default();
+        active_device.set_if_neq(ActiveInputDevice::Gamepad(family));
+    } else if keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || moved_mouse
+    {
+        active_device.set_if_neq(ActiveInputDevice::KeyboardMouse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyphs_follow_device_and_bindings() {
+        let mut profile = BindingProfile::default();
+
+        let keyboard = ActiveInputDevice::KeyboardMouse;
+        assert_eq!(
+            keyboard.glyph(&profile, PlayerAction::Crouch).as_deref(),
+            Some("key_shift_left")
+        );
+        assert_eq!(
+            keyboard.glyphs(&profile, PlayerAction::Move),
+            vec!["key_w", "key_a", "key_s", "key_d"]
+        );
+
+        let playstation = ActiveInputDevice::Gamepad(GamepadFamily::from_name("DualSense"));
+        assert_eq!(
+            playstation.glyph(&profile, PlayerAction::Jump).as_deref(),
+            Some("ps_cross")
+        );
+
+        profile.bind_button(PlayerAction::Jump, GamepadButtonType::DPadUp);
+        let generic = ActiveInputDevice::Gamepad(GamepadFamily::Generic);
+        assert_eq!(
+            generic.glyph(&profile, PlayerAction::Jump).as_deref(),
+            Some("gamepad_d_pad_up")
+        );
+        assert_eq!(
+            generic.glyph(&profile, PlayerAction::CamModeChangePositive),
+            None
+        );
+    }
+}
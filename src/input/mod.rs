@@ -0,0 +1,367 @@
+use crate::types::EngineSystemSet;
+use bevy::{prelude::*, utils::HashMap};
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+pub mod bindings;
+pub mod combo;
+pub mod context;
+pub mod device;
+pub mod replay;
+pub mod slots;
+pub mod stick;
+
+use bindings::BindingProfile;
+use context::InputContextStack;
+use slots::InputDevice;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(InputManagerPlugin::<PlayerAction>::default())
+            .add_plugins((
+                bindings::BindingsPlugin,
+                replay::ReplayPlugin,
+                combo::ComboPlugin,
+                context::InputContextPlugin,
+                device::ActiveDevicePlugin,
+                slots::PlayerSlotPlugin,
+                stick::StickPlugin,
+            ))
+            .add_systems(FixedUpdate, buffer_inputs.in_set(EngineSystemSet::Input));
+    }
+}
+
+#[derive(
+    Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Default, Reflect, Serialize, Deserialize,
+)]
+pub enum PlayerAction {
+    #[default]
+    Jump,
+    Move,
+    Interact,
+    Crouch,
+    CamRotateRight,
+    CamRotateLeft,
+    CamModeChangePositive,
+    CamModeChangeNegative,
+    /// Right stick tilt, a rate
+    CamOrbit,
+    /// Mouse motion, a per frame delta
+    CamOrbitMouse,
+    LockOn,
+    CycleTarget,
+    PhotoMode,
+}
+
+const DEFAULT_BUFFER_WINDOW: Duration = Duration::from_millis(166);
+const PRESS_HISTORY_LENGTH: usize = 8;
+
+#[derive(Component, Default)]
+pub struct InputBuffer {
+    pressed_actions: HashSet<PlayerAction>,
+    stale_actions: HashSet<PlayerAction>,
+    buffered_actions: HashMap<PlayerAction, Timer>,
+    buffer_windows: HashMap<PlayerAction, Duration>,
+    elapsed: Duration,
+    press_history: VecDeque<(PlayerAction, Duration)>,
+    press_started: HashMap<PlayerAction, Duration>,
+    last_release: HashMap<PlayerAction, (Duration, Duration)>,
+}
+
+#[allow(unused)]
+impl InputBuffer {
+    pub fn with_buffer_window(mut self, action: PlayerAction, window: Duration) -> Self {
+        self.set_buffer_window(action, window);
+        self
+    }
+
+    pub fn set_buffer_window(&mut self, action: PlayerAction, window: Duration) {
+        self.buffer_windows.insert(action, window);
+    }
+
+    pub fn buffer_window(&self, action: PlayerAction) -> Duration {
+        self.buffer_windows
+            .get(&action)
+            .copied()
+            .unwrap_or(DEFAULT_BUFFER_WINDOW)
+    }
+
+    pub fn just_pressed(&self, action: PlayerAction) -> bool {
+        match self.pressed_actions.get(&action) {
+            Some(_) => match self.stale_actions.get(&action) {
+                None => return true,
+                _ => (),
+            },
+            _ => (),
+        }
+
+        match self.buffered_actions.get(&action) {
+            Some(_) => match self.stale_actions.get(&action) {
+                Some(_) => false,
+                None => true,
+            },
+            None => false,
+        }
+    }
+
+    pub fn pressed(&self, action: PlayerAction) -> bool {
+        self.pressed_actions.get(&action).is_some()
+    }
+
+    pub fn released(&self, action: PlayerAction) -> bool {
+        self.pressed_actions.get(&action).is_none() && self.buffered_actions.get(&action).is_none()
+    }
+
+    /// How long `action` has been held, zero if it isn't pressed
+    pub fn held_for(&self, action: PlayerAction) -> Duration {
+        match self.press_started.get(&action) {
+            Some(started) => self.elapsed - *started,
+            None => Duration::ZERO,
+        }
+    }
+
+    /// True on the tick `action` was released
+    pub fn just_released(&self, action: PlayerAction) -> bool {
+        self.last_release
+            .get(&action)
+            .is_some_and(|(released_at, _)| *released_at == self.elapsed)
+    }
+
+    /// True on the tick `action` was released if it had been held for at least `duration`, for
+    /// charged actions and holds
+    pub fn released_after(&self, action: PlayerAction, duration: Duration) -> bool {
+        self.just_released(action)
+            && self
+                .last_release
+                .get(&action)
+                .is_some_and(|(_, held)| *held >= duration)
+    }
+
+    /// True on the tick `action` was released if it had been held for less than `duration`, for
+    /// telling taps apart from holds
+    pub fn released_before(&self, action: PlayerAction, duration: Duration) -> bool {
+        self.just_released(action)
+            && self
+                .last_release
+                .get(&action)
+                .is_some_and(|(_, held)| *held < duration)
+    }
+
+    /// Returns true and marks the press as used if `action` was just pressed, so only the first
+    /// system to consume a buffered press gets to act on it
+    pub fn consume(&mut self, action: PlayerAction) -> bool {
+        if self.just_pressed(action) {
+            self.buffered_actions.remove(&action);
+            self.stale_actions.insert(action);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The most recent presses, oldest first, with the buffer time they happened at
+    pub fn press_history(&self) -> impl Iterator<Item = &(PlayerAction, Duration)> {
+        self.press_history.iter()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn press(&mut self, action: PlayerAction) {
+        if self.press_history.len() == PRESS_HISTORY_LENGTH {
+            self.press_history.pop_front();
+        }
+        self.press_history.push_back((action, self.elapsed));
+        self.buffered_actions.insert(
+            action,
+            Timer::new(self.buffer_window(action), TimerMode::Once),
+        );
+        self.stale_actions.remove(&action);
+        self.pressed_actions.insert(action);
+        self.press_started.insert(action, self.elapsed);
+    }
+
+    pub fn release(&mut self, action: PlayerAction) {
+        self.buffered_actions.remove(&action);
+        self.stale_actions.remove(&action);
+        self.pressed_actions.remove(&action);
+        if let Some(started) = self.press_started.remove(&action) {
+            self.last_release
+                .insert(action, (self.elapsed, self.elapsed - started));
+        }
+    }
+
+    /// Releases every held action, used when another input context takes over so nothing is
+    /// left held down or buffered when control comes back
+    pub fn release_all(&mut self) {
+        self.buffered_actions.clear();
+        self.stale_actions.clear();
+        self.pressed_actions.clear();
+        self.press_started.clear();
+        self.last_release.clear();
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.elapsed += delta;
+        let mut stale_buffers: Vec<PlayerAction> = Vec::new();
+        self.buffered_actions
+            .iter_mut()
+            .for_each(|(action, timer)| {
+                timer.tick(delta);
+                match timer.finished() {
+                    true => {
+                        self.stale_actions.insert(*action);
+                        stale_buffers.push(*action);
+                    }
+                    _ => (),
+                }
+            });
+        for action in stale_buffers.iter() {
+            self.buffered_actions.remove(action);
+            self.stale_actions.insert(*action);
+        }
+    }
+}
+#[derive(Bundle)]
+pub struct InputListenerBundle {
+    input_manager: InputManagerBundle<PlayerAction>,
+}
+
+impl InputListenerBundle {
+    pub fn from_profile(profile: &BindingProfile, device: InputDevice) -> InputListenerBundle {
+        InputListenerBundle {
+            input_manager: InputManagerBundle {
+                input_map: profile.input_map(device),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+pub(crate) fn buffer_inputs(
+    time: Res<Time>,
+    context: Res<InputContextStack>,
+    mut input_buffer_query: Query<(&mut InputBuffer, &ActionState<PlayerAction>)>,
+) {
+    for (mut buffer, input) in &mut input_buffer_query {
+        if !context.top().uses_player_actions() {
+            buffer.release_all();
+            continue;
+        }
+
+        buffer.tick(time.delta());
+
+        //Handle Presses
+        // The ActionState only updates once a frame, so when a frame runs several fixed ticks
+        // they all see the same just pressed actions. Only the first tick records the press.
+        for action in input.get_just_pressed().iter() {
+            if !buffer.pressed(*action) {
+                buffer.press(*action);
+            }
+        }
+
+        //Handle Releases
+        for action in input.get_just_released().iter() {
+            buffer.release(*action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_is_buffered_until_window_ends() {
+        let mut buffer = InputBuffer::default();
+        buffer.press(PlayerAction::Jump);
+        assert!(buffer.just_pressed(PlayerAction::Jump));
+        assert!(buffer.pressed(PlayerAction::Jump));
+
+        buffer.tick(Duration::from_millis(100));
+        assert!(buffer.just_pressed(PlayerAction::Jump));
+
+        buffer.tick(Duration::from_millis(100));
+        assert!(!buffer.just_pressed(PlayerAction::Jump));
+        assert!(buffer.pressed(PlayerAction::Jump));
+        assert!(!buffer.released(PlayerAction::Jump));
+    }
+
+    #[test]
+    fn release_clears_stale_press() {
+        let mut buffer = InputBuffer::default();
+        buffer.press(PlayerAction::Jump);
+        buffer.tick(Duration::from_secs(1));
+        assert!(!buffer.just_pressed(PlayerAction::Jump));
+
+        buffer.release(PlayerAction::Jump);
+        assert!(buffer.released(PlayerAction::Jump));
+        assert!(!buffer.pressed(PlayerAction::Jump));
+
+        buffer.press(PlayerAction::Jump);
+        assert!(buffer.just_pressed(PlayerAction::Jump));
+    }
+
+    #[test]
+    fn per_action_buffer_windows() {
+        let mut buffer = InputBuffer::default()
+            .with_buffer_window(PlayerAction::Jump, Duration::from_millis(300))
+            .with_buffer_window(PlayerAction::Interact, Duration::from_millis(50));
+        buffer.press(PlayerAction::Jump);
+        buffer.press(PlayerAction::Interact);
+        buffer.press(PlayerAction::Crouch);
+
+        buffer.tick(Duration::from_millis(200));
+        assert!(buffer.just_pressed(PlayerAction::Jump));
+        assert!(!buffer.just_pressed(PlayerAction::Interact));
+        assert!(!buffer.just_pressed(PlayerAction::Crouch));
+    }
+
+    #[test]
+    fn buffered_press_is_consumed_once() {
+        let mut buffer = InputBuffer::default();
+        buffer.press(PlayerAction::Interact);
+
+        assert!(buffer.consume(PlayerAction::Interact));
+        assert!(!buffer.consume(PlayerAction::Interact));
+        assert!(!buffer.just_pressed(PlayerAction::Interact));
+        assert!(buffer.pressed(PlayerAction::Interact));
+
+        buffer.release(PlayerAction::Interact);
+        buffer.press(PlayerAction::Interact);
+        assert!(buffer.consume(PlayerAction::Interact));
+    }
+
+    #[test]
+    fn hold_duration_and_tap_or_hold() {
+        let mut buffer = InputBuffer::default();
+        assert_eq!(buffer.held_for(PlayerAction::Crouch), Duration::ZERO);
+
+        buffer.press(PlayerAction::Crouch);
+        buffer.tick(Duration::from_millis(300));
+        assert_eq!(
+            buffer.held_for(PlayerAction::Crouch),
+            Duration::from_millis(300)
+        );
+
+        buffer.release(PlayerAction::Crouch);
+        assert_eq!(buffer.held_for(PlayerAction::Crouch), Duration::ZERO);
+        assert!(buffer.just_released(PlayerAction::Crouch));
+        assert!(buffer.released_after(PlayerAction::Crouch, Duration::from_millis(250)));
+        assert!(!buffer.released_before(PlayerAction::Crouch, Duration::from_millis(250)));
+
+        buffer.tick(Duration::from_millis(16));
+        assert!(!buffer.just_released(PlayerAction::Crouch));
+        assert!(!buffer.released_after(PlayerAction::Crouch, Duration::ZERO));
+
+        buffer.press(PlayerAction::Interact);
+        buffer.tick(Duration::from_millis(100));
+        buffer.release(PlayerAction::Interact);
+        assert!(buffer.released_before(PlayerAction::Interact, Duration::from_millis(250)));
+    }
+}
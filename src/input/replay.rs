@@ -0,0 +1,340 @@
+use super::{slots::PlayerSlot, PlayerAction};
+use crate::player::PlayerData;
+use crate::types::EngineSystemSet;
+use crate::GameState;
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+use bevy_xpbd_3d::prelude::Physics;
+use leafwing_input_manager::{
+    action_state::ActionData, axislike::DualAxisData, buttonlike::ButtonState, prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .add_systems(Startup, start_replay_mode)
+            .add_systems(
+                FixedUpdate,
+                (
+                    record_inputs.run_if(resource_exists::<InputRecorder>),
+                    replay_inputs.run_if(resource_exists::<InputReplay>),
+                )
+                    .before(EngineSystemSet::Input)
+                    .run_if(in_state(GameState::Overworld)),
+            )
+            .add_systems(
+                Last,
+                save_recording_on_exit.run_if(resource_exists::<InputRecorder>),
+            );
+    }
+}
+
+/// How the game sources player input, chosen from the command line:
+///
+/// - `--record <file>` records live input to `file` when the game exits
+/// - `--replay <file>` plays `file` back in place of live input
+/// - `--headless-replay <file> <output>` plays `file` back without a window and writes the
+///   final [`PlayerData`] to `output`
+///
+/// Only the primary player is recorded and replayed.
+#[derive(Resource, Clone, Default, Debug)]
+pub enum ReplayMode {
+    #[default]
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+    Headless {
+        recording: PathBuf,
+        output: PathBuf,
+    },
+}
+
+impl ReplayMode {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let value_after = |flag: &str, offset: usize| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + offset))
+                .map(PathBuf::from)
+        };
+
+        if let (Some(recording), Some(output)) = (
+            value_after("--headless-replay", 1),
+            value_after("--headless-replay", 2),
+        ) {
+            ReplayMode::Headless { recording, output }
+        } else if let Some(recording) = value_after("--replay", 1) {
+            ReplayMode::Replay(recording)
+        } else if let Some(recording) = value_after("--record", 1) {
+            ReplayMode::Record(recording)
+        } else {
+            ReplayMode::Live
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        matches!(self, ReplayMode::Headless { .. })
+    }
+}
+
+/// The state of every pressed [`PlayerAction`] on a single fixed tick
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub pressed: Vec<PlayerAction>,
+    /// Actions that were just pressed or just released on this tick. Several fixed ticks can run
+    /// in one frame and each sees the same just pressed state, so it's recorded as it was rather
+    /// than worked out from the previous tick.
+    pub just_pressed: Vec<PlayerAction>,
+    pub just_released: Vec<PlayerAction>,
+    pub move_axis: Option<Vec2>,
+    #[serde(default)]
+    pub orbit_axis: Option<Vec2>,
+    #[serde(default)]
+    pub orbit_mouse_axis: Option<Vec2>,
+}
+
+impl RecordedFrame {
+    /// How `action` should be replayed on this tick
+    fn button_state(&self, action: PlayerAction) -> ButtonState {
+        if self.just_pressed.contains(&action) {
+            ButtonState::JustPressed
+        } else if self.pressed.contains(&action) {
+            ButtonState::Pressed
+        } else if self.just_released.contains(&action) {
+            ButtonState::JustReleased
+        } else {
+            ButtonState::Released
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct InputRecording {
+    pub timestep: Duration,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        std::fs::write(path, contents).map_err(|error| error.to_string())
+    }
+}
+
+#[derive(Resource)]
+pub struct InputRecorder {
+    path: PathBuf,
+    recording: InputRecording,
+}
+
+#[derive(Resource)]
+pub struct InputReplay {
+    recording: InputRecording,
+    current_frame: usize,
+    output: Option<PathBuf>,
+}
+
+impl InputReplay {
+    pub fn finished(&self) -> bool {
+        self.current_frame >= self.recording.frames.len()
+    }
+}
+
+fn start_replay_mode(
+    mut commands: Commands,
+    mode: Res<ReplayMode>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut toggle_actions: ResMut<ToggleActions<PlayerAction>>,
+) {
+    let (recording_path, output) = match mode.as_ref() {
+        ReplayMode::Live => return,
+        ReplayMode::Record(path) => {
+            commands.insert_resource(InputRecorder {
+                path: path.clone(),
+                recording: InputRecording {
+                    timestep: fixed_time.timestep(),
+                    frames: Vec::new(),
+                },
+            });
+            return;
+        }
+        ReplayMode::Replay(recording) => (recording, None),
+        ReplayMode::Headless { recording, output } => (recording, Some(output.clone())),
+    };
+
+    match InputRecording::load(recording_path) {
+        Ok(recording) => {
+            fixed_time.set_timestep(recording.timestep);
+            if mode.is_headless() {
+                // Step input and physics exactly once per frame so the replay doesn't depend on
+                // how many frames loading took
+                commands.insert_resource(TimeUpdateStrategy::ManualDuration(recording.timestep));
+                commands.insert_resource(Time::new_with(Physics::fixed_once_hz(
+                    1.0 / recording.timestep.as_secs_f64(),
+                )));
+            }
+            // Live input is ignored for as long as the replay owns the ActionState
+            *toggle_actions = ToggleActions::DISABLED;
+            commands.insert_resource(InputReplay {
+                recording,
+                current_frame: 0,
+                output,
+            });
+        }
+        Err(error) => error!(
+            "Failed to load input recording {}: {}",
+            recording_path.display(),
+            error
+        ),
+    }
+}
+
+fn record_inputs(
+    mut recorder: ResMut<InputRecorder>,
+    action_query: Query<(&ActionState<PlayerAction>, &PlayerSlot)>,
+) {
+    for (action_state, _) in action_query.iter().filter(|(_, slot)| slot.is_primary()) {
+        let axis = |action| {
+            action_state
+                .axis_pair(&action)
+                .map(|axis_pair| axis_pair.xy())
+        };
+
+        recorder.recording.frames.push(RecordedFrame {
+            pressed: action_state.get_pressed(),
+            just_pressed: action_state.get_just_pressed(),
+            just_released: action_state.get_just_released(),
+            move_axis: axis(PlayerAction::Move),
+            orbit_axis: axis(PlayerAction::CamOrbit),
+            orbit_mouse_axis: axis(PlayerAction::CamOrbitMouse),
+        });
+    }
+}
+
+fn replay_inputs(
+    mut commands: Commands,
+    mut replay: ResMut<InputReplay>,
+    mut toggle_actions: ResMut<ToggleActions<PlayerAction>>,
+    mut action_query: Query<(&mut ActionState<PlayerAction>, &PlayerSlot)>,
+    mut app_exit: EventWriter<AppExit>,
+    player_data: Res<PlayerData>,
+) {
+    if replay.finished() {
+        if let Some(output) = &replay.output {
+            match ron::ser::to_string_pretty(player_data.as_ref(), Default::default()) {
+                Ok(contents) => {
+                    if let Err(error) = std::fs::write(output, contents) {
+                        error!("Failed to write {}: {}", output.display(), error);
+                    }
+                }
+                Err(error) => error!("Failed to serialize player data: {}", error),
+            }
+            app_exit.send(AppExit);
+        }
+        *toggle_actions = ToggleActions::ENABLED;
+        commands.remove_resource::<InputReplay>();
+        return;
+    }
+
+    let frame = &replay.recording.frames[replay.current_frame];
+    for (mut action_state, slot) in &mut action_query {
+        if !slot.is_primary() {
+            continue;
+        }
+
+        let mut actions = action_state.keys();
+        for action in frame.pressed.iter().chain(&frame.just_released) {
+            if !actions.contains(action) {
+                actions.push(*action);
+            }
+        }
+
+        for action in actions {
+            let is_pressed = frame.pressed.contains(&action);
+            let state = frame.button_state(action);
+
+            let axis_pair = match action {
+                PlayerAction::Move if is_pressed => frame.move_axis.map(DualAxisData::from_xy),
+                PlayerAction::CamOrbit if is_pressed => frame.orbit_axis.map(DualAxisData::from_xy),
+                PlayerAction::CamOrbitMouse if is_pressed => {
+                    frame.orbit_mouse_axis.map(DualAxisData::from_xy)
+                }
+                _ => None,
+            };
+
+            action_state.set_action_data(
+                action,
+                ActionData {
+                    state,
+                    value: if is_pressed { 1.0 } else { 0.0 },
+                    axis_pair,
+                    ..default()
+                },
+            );
+        }
+    }
+
+    replay.current_frame += 1;
+}
+
+fn save_recording_on_exit(recorder: Res<InputRecorder>, mut app_exit: EventReader<AppExit>) {
+    if app_exit.read().next().is_some() {
+        match recorder.recording.save(&recorder.path) {
+            Ok(()) => info!(
+                "Saved {} recorded frames to {}",
+                recorder.recording.frames.len(),
+                recorder.path.display()
+            ),
+            Err(error) => error!(
+                "Failed to save input recording {}: {}",
+                recorder.path.display(),
+                error
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presses_replay_as_recorded() {
+        let frame = RecordedFrame {
+            pressed: vec![PlayerAction::Jump],
+            just_pressed: vec![PlayerAction::Jump],
+            just_released: vec![PlayerAction::Crouch],
+            ..default()
+        };
+        // A second fixed tick in the same frame still sees the press as just pressed
+        assert_eq!(
+            frame.button_state(PlayerAction::Jump),
+            ButtonState::JustPressed
+        );
+        assert_eq!(
+            frame.button_state(PlayerAction::Crouch),
+            ButtonState::JustReleased
+        );
+        assert_eq!(
+            frame.button_state(PlayerAction::Interact),
+            ButtonState::Released
+        );
+
+        let held = RecordedFrame {
+            pressed: vec![PlayerAction::Jump],
+            ..default()
+        };
+        assert_eq!(held.button_state(PlayerAction::Jump), ButtonState::Pressed);
+    }
+}
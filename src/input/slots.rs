@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+
+pub struct PlayerSlotPlugin;
+
+impl Plugin for PlayerSlotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerSlots>()
+            .register_type::<PlayerSlot>()
+            .add_systems(
+                PreUpdate,
+                assign_gamepads
+                    .after(bevy::input::InputSystem)
+                    .run_if(resource_changed::<Gamepads>),
+            );
+    }
+}
+
+pub const MAX_PLAYERS: usize = 4;
+
+/// Which local player an entity is controlled by, slot 0 is always the keyboard player
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct PlayerSlot(pub usize);
+
+impl PlayerSlot {
+    pub fn is_primary(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputDevice {
+    pub keyboard: bool,
+    pub gamepad: Option<Gamepad>,
+}
+
+/// The devices assigned to each local player, indexed by [`PlayerSlot`]
+#[derive(Resource)]
+pub struct PlayerSlots(Vec<InputDevice>);
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        PlayerSlots(vec![InputDevice {
+            keyboard: true,
+            gamepad: None,
+        }])
+    }
+}
+
+impl PlayerSlots {
+    pub fn device(&self, slot: PlayerSlot) -> InputDevice {
+        self.0.get(slot.0).copied().unwrap_or_default()
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = PlayerSlot> {
+        (0..self.0.len()).map(PlayerSlot)
+    }
+
+    fn slot_for_gamepad(&self, gamepad: Gamepad) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|device| device.gamepad == Some(gamepad))
+    }
+
+    /// Gives `gamepad` to a player who lost theirs, then to the keyboard player, and otherwise
+    /// opens a new slot for it
+    fn assign(&mut self, gamepad: Gamepad) {
+        let stranded = self
+            .0
+            .iter()
+            .position(|device| !device.keyboard && device.gamepad.is_none());
+        let without_gamepad = self.0.iter().position(|device| device.gamepad.is_none());
+
+        if let Some(index) = stranded.or(without_gamepad) {
+            self.0[index].gamepad = Some(gamepad);
+        } else if self.0.len() < MAX_PLAYERS {
+            self.0.push(InputDevice {
+                keyboard: false,
+                gamepad: Some(gamepad),
+            });
+        }
+    }
+}
+
+fn assign_gamepads(gamepads: Res<Gamepads>, mut slots: ResMut<PlayerSlots>) {
+    for device in slots.0.iter_mut() {
+        if let Some(gamepad) = device.gamepad {
+            if !gamepads.contains(gamepad) {
+                info!("Gamepad {} disconnected", gamepad.id);
+                device.gamepad = None;
+            }
+        }
+    }
+
+    for gamepad in gamepads.iter() {
+        if slots.slot_for_gamepad(gamepad).is_none() {
+            slots.assign(gamepad);
+            info!("Gamepad {} assigned to a player slot", gamepad.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::bindings::{apply_binding_profile, BindingProfile};
+    use crate::input::PlayerAction;
+    use bevy::input::gamepad::{
+        gamepad_connection_system, GamepadConnection, GamepadConnectionEvent, GamepadInfo,
+    };
+    use bevy::input::InputSystem;
+    use leafwing_input_manager::prelude::*;
+
+    fn connect(app: &mut App, id: usize) {
+        app.world.send_event(GamepadConnectionEvent::new(
+            Gamepad::new(id),
+            GamepadConnection::Connected(GamepadInfo {
+                name: format!("Gamepad {}", id),
+            }),
+        ));
+        app.update();
+    }
+
+    fn disconnect(app: &mut App, id: usize) {
+        app.world.send_event(GamepadConnectionEvent::new(
+            Gamepad::new(id),
+            GamepadConnection::Disconnected,
+        ));
+        app.update();
+    }
+
+    /// Whether the player in `slot` listens to the keyboard, and which gamepad they listen to
+    fn bound_devices(app: &mut App, slot: usize) -> (bool, Option<Gamepad>) {
+        let mut query = app.world.query::<(&InputMap<PlayerAction>, &PlayerSlot)>();
+        let (input_map, _) = query
+            .iter(&app.world)
+            .find(|(_, player_slot)| player_slot.0 == slot)
+            .unwrap();
+
+        let keyboard = input_map.get(&PlayerAction::Jump).is_some_and(|inputs| {
+            inputs
+                .iter()
+                .any(|input| matches!(input, UserInput::Single(InputKind::PhysicalKey(_))))
+        });
+        (keyboard, input_map.gamepad())
+    }
+
+    fn slot_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(PlayerSlotPlugin)
+            .add_event::<GamepadConnectionEvent>()
+            .init_resource::<Gamepads>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<Axis<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .insert_resource(BindingProfile::default())
+            .add_systems(PreUpdate, gamepad_connection_system.in_set(InputSystem))
+            .add_systems(
+                Update,
+                apply_binding_profile.run_if(resource_changed::<PlayerSlots>),
+            );
+
+        // Players for the first two slots, as the player module spawns them when slots open
+        for slot in 0..2 {
+            app.world
+                .spawn((PlayerSlot(slot), InputMap::<PlayerAction>::default()));
+        }
+        app.update();
+        app
+    }
+
+    #[test]
+    fn first_gamepad_joins_the_keyboard_player() {
+        let mut app = slot_app();
+        assert_eq!(bound_devices(&mut app, 0), (true, None));
+
+        connect(&mut app, 0);
+        let slots = app.world.resource::<PlayerSlots>();
+        assert_eq!(slots.slots().count(), 1);
+        assert_eq!(bound_devices(&mut app, 0), (true, Some(Gamepad::new(0))));
+    }
+
+    #[test]
+    fn further_gamepads_hot_join_as_new_players() {
+        let mut app = slot_app();
+        connect(&mut app, 0);
+        connect(&mut app, 1);
+
+        assert_eq!(app.world.resource::<PlayerSlots>().slots().count(), 2);
+        assert_eq!(bound_devices(&mut app, 0), (true, Some(Gamepad::new(0))));
+        assert_eq!(bound_devices(&mut app, 1), (false, Some(Gamepad::new(1))));
+    }
+
+    #[test]
+    fn disconnected_slots_get_the_next_gamepad() {
+        let mut app = slot_app();
+        connect(&mut app, 0);
+        connect(&mut app, 1);
+
+        // The keyboard player keeps the keyboard when their gamepad goes
+        disconnect(&mut app, 0);
+        assert_eq!(bound_devices(&mut app, 0), (true, None));
+        assert_eq!(bound_devices(&mut app, 1), (false, Some(Gamepad::new(1))));
+
+        // A gamepad-only player is left with nothing, so they're first in line for a new one
+        disconnect(&mut app, 1);
+        assert_eq!(bound_devices(&mut app, 1), (false, None));
+        connect(&mut app, 2);
+        assert_eq!(bound_devices(&mut app, 0), (true, None));
+        assert_eq!(bound_devices(&mut app, 1), (false, Some(Gamepad::new(2))));
+
+        // Then the keyboard player, rather than opening another slot
+        connect(&mut app, 3);
+        assert_eq!(app.world.resource::<PlayerSlots>().slots().count(), 2);
+        assert_eq!(bound_devices(&mut app, 0), (true, Some(Gamepad::new(3))));
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+pub struct StickPlugin;
+
+impl Plugin for StickPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StickSettings>()
+            .register_type::<StickSettings>();
+    }
+}
+
+/// How raw analog stick input is turned into movement
+#[derive(Resource, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct StickSettings {
+    /// Tilt below this is treated as no input, so a worn stick doesn't drift
+    pub inner_deadzone: f32,
+    /// Tilt above this is treated as full tilt, since most sticks never quite reach 1.0
+    pub outer_deadzone: f32,
+    /// Exponent applied to the tilt between the dead-zones, higher values give finer control at
+    /// low tilt
+    pub response_exponent: f32,
+    /// Processed tilt below this walks instead of running
+    pub walk_threshold: f32,
+    /// The share of full speed a walk reaches just under the walk threshold
+    pub walk_speed: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            inner_deadzone: 0.15,
+            outer_deadzone: 0.95,
+            response_exponent: 1.5,
+            walk_threshold: 0.6,
+            walk_speed: 0.4,
+        }
+    }
+}
+
+impl StickSettings {
+    /// Applies the radial dead-zones and response curve, keeping the stick's direction
+    pub fn process(&self, axis: Vec2) -> Vec2 {
+        let magnitude = axis.length();
+        if magnitude <= self.inner_deadzone {
+            return Vec2::ZERO;
+        }
+
+        let range = (self.outer_deadzone - self.inner_deadzone).max(f32::EPSILON);
+        let scaled = ((magnitude - self.inner_deadzone) / range).clamp(0.0, 1.0);
+
+        axis / magnitude * scaled.powf(self.response_exponent)
+    }
+
+    /// The share of full speed a processed tilt of `magnitude` moves at. Tilt below the walk
+    /// threshold spreads over the walk speed range, past it the character breaks into a full run,
+    /// so there's a deliberate step from walking to running rather than a slow jog in between.
+    pub fn throttle(&self, magnitude: f32) -> f32 {
+        if magnitude >= self.walk_threshold {
+            1.0
+        } else {
+            magnitude / self.walk_threshold.max(f32::EPSILON) * self.walk_speed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zones_and_curve() {
+        let settings = StickSettings::default();
+
+        assert_eq!(settings.process(Vec2::new(0.1, 0.05)), Vec2::ZERO);
+        assert_eq!(settings.process(Vec2::new(0.0, 0.97)), Vec2::Y);
+
+        let half = settings.process(Vec2::new(-0.55, 0.0));
+        assert!(half.x < 0.0 && half.x > -0.5);
+        assert_eq!(half.y, 0.0);
+    }
+
+    #[test]
+    fn throttle_walks_below_the_threshold_and_runs_above_it() {
+        let settings = StickSettings::default();
+
+        assert_eq!(settings.throttle(0.0), 0.0);
+        assert_eq!(settings.throttle(0.3), 0.2);
+        let walking = settings.throttle(settings.walk_threshold - 0.01);
+        assert!(walking < settings.walk_speed && walking > settings.walk_speed - 0.01);
+
+        assert_eq!(settings.throttle(settings.walk_threshold), 1.0);
+        assert_eq!(settings.throttle(1.0), 1.0);
+    }
+}
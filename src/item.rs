@@ -71,12 +71,12 @@ fn pickup_items(
     collisions: Res<Collisions>,
     // a resource that holds the players inventory
     mut inventory: ResMut<Inventory>,
-    // A query that finds the player entity
-    player_query: Query<(Entity, &InputBuffer), With<Player>>,
+    // A query that finds every player entity
+    mut player_query: Query<(Entity, &mut InputBuffer), With<Player>>,
     // A query that finds all entities with an OverWorldItem Component
     item_query: Query<&OverworldItem>,
 ) {
-    if let Ok((player_entity, intput_buffer)) = player_query.get_single() {
+    for (player_entity, mut intput_buffer) in &mut player_query {
         // 1. Use collisions to find all entities colliding with the player entity
         for collision in collisions.collisions_with_entity(player_entity) {
             // 2. Check those collision pairs to see if one of the entites is the player and the other is
@@ -90,7 +90,7 @@ fn pickup_items(
 
             if let Ok(overworld_item) = item_query.get(item_entity) {
                 // 3. if the player is pressing the interact button
-                if intput_buffer.just_pressed(crate::input::PlayerAction::Interact) {
+                if intput_buffer.consume(crate::input::PlayerAction::Interact) {
                     // 4. Add the item to the players inventory
                     // add overworld item to inventory
                     inventory.add_to_inventory(overworld_item.id);
@@ -15,30 +15,74 @@ mod types;
 mod ui;
 
 fn main() {
+    let replay_mode = input::replay::ReplayMode::from_args();
+    let headless = replay_mode.is_headless();
     let mut app = App::new();
-    app.add_plugins((
-        DefaultPlugins,
-        bevy_xpbd_3d::prelude::PhysicsPlugins::default(),
-    ))
-    .add_plugins((
-        input::InputPlugin,
-        camera::CameraPlugin,
-        player::PlayerPlugin,
-        physics::PhysicsPlugin,
-        assets::AssetPlugin,
-        animation::AnimationPlugin,
-        environment::EnvironmentPlugin,
-        item::ItemPlugin,
-        ui::BaseUiPlugin,
-    ))
-    .insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 50.0,
-    })
-    .register_type::<animation::Animated>()
-    .insert_state(GameState::Preload)
-    .add_systems(Startup, setup)
-    .add_systems(OnEnter(GameState::Overworld), post_load_spawn);
+    if headless {
+        use bevy::ecs::schedule::{ExecutorKind, ScheduleLabel};
+        use bevy::render::{settings::WgpuSettings, RenderPlugin};
+        use bevy::window::ExitCondition;
+
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .set(TaskPoolPlugin {
+                    task_pool_options: TaskPoolOptions::with_num_threads(1),
+                })
+                .disable::<bevy::winit::WinitPlugin>(),
+        )
+        .add_plugins(bevy::app::ScheduleRunnerPlugin::run_loop(
+            std::time::Duration::ZERO,
+        ))
+        .insert_resource(camera::CameraData::default());
+        // Run ambiguous systems in a fixed order so replays are reproducible
+        for label in [
+            PreUpdate.intern(),
+            FixedUpdate.intern(),
+            Update.intern(),
+            PostUpdate.intern(),
+        ] {
+            app.edit_schedule(label, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        }
+    } else {
+        app.add_plugins(DefaultPlugins)
+            .add_plugins((camera::CameraPlugin, ui::BaseUiPlugin));
+    }
+    app.insert_resource(replay_mode)
+        .add_plugins(bevy_xpbd_3d::prelude::PhysicsPlugins::default())
+        .add_plugins((
+            input::InputPlugin,
+            player::PlayerPlugin,
+            physics::PhysicsPlugin,
+            assets::AssetPlugin {
+                export_registry: !headless,
+            },
+            animation::AnimationPlugin,
+            environment::EnvironmentPlugin,
+            item::ItemPlugin,
+        ))
+        .insert_resource(AmbientLight {
+            color: Color::WHITE,
+            brightness: 50.0,
+        })
+        .register_type::<animation::Animated>()
+        .insert_state(GameState::Preload)
+        .add_systems(Startup, setup)
+        .add_systems(OnEnter(GameState::Overworld), post_load_spawn);
     #[cfg(feature = "debug-render")]
     {
         println!("Debug Renderer eneabled");
@@ -10,6 +10,7 @@ pub enum CollisionLayer {
     Terrain,
     AreaTransition,
     Item,
+    CameraZone,
 }
 
 impl CollisionLayer {
@@ -17,6 +18,11 @@ impl CollisionLayer {
         LayerMask(0b1010)
     }
 
+    /// The layers the camera can't pass through
+    pub fn camera_blocking_mask() -> LayerMask {
+        LayerMask::from([CollisionLayer::Terrain, CollisionLayer::Object])
+    }
+
     pub fn terrain_layers() -> CollisionLayers {
         CollisionLayers::new(
             CollisionLayer::Terrain,
@@ -57,6 +63,10 @@ impl CollisionLayer {
         CollisionLayers::new(CollisionLayer::Item, [CollisionLayer::Character])
     }
 
+    pub fn camera_zone_layers() -> CollisionLayers {
+        CollisionLayers::new(CollisionLayer::CameraZone, [CollisionLayer::Character])
+    }
+
     pub fn transition_layers() -> CollisionLayers {
         CollisionLayers::new(CollisionLayer::AreaTransition, [CollisionLayer::Character])
     }
@@ -1,4 +1,5 @@
 use super::types::*;
+use crate::input::context::gameplay_running;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_xpbd_3d::{math::Quaternion, prelude::*};
@@ -17,7 +18,8 @@ impl Plugin for PhysicsSystemPlugin {
                 lateral_damping,
                 handle_coyote_time,
             )
-                .run_if(in_state(GameState::Overworld)),
+                .run_if(in_state(GameState::Overworld))
+                .run_if(gameplay_running),
         );
     }
 }
@@ -117,7 +119,7 @@ fn floating_capsule(
             force.set_force(applied_force);
             if !has_grounded && !has_jumping {
                 commands.entity(entity).insert(Grounded);
-                landing_events.send(LandingEvent(entity));
+                landing_events.send(LandingEvent(entity, velocity.y));
             }
         } else {
             if has_grounded {
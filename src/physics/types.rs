@@ -83,6 +83,7 @@ pub struct MoveSpeed {
     max_speed: f32,
     accelerate_timer: Timer,
     decelerate_timer: Timer,
+    throttle: f32,
 }
 
 impl MoveSpeed {
@@ -95,11 +96,22 @@ impl MoveSpeed {
             max_speed: base_speed * 2.0,
             accelerate_timer: Timer::from_seconds(0.3, TimerMode::Once),
             decelerate_timer: Timer::from_seconds(0.2, TimerMode::Once),
+            throttle: 1.0,
         }
     }
 
     pub fn get(&self) -> f32 {
-        self.current_speed
+        self.current_speed * self.throttle
+    }
+
+    /// How close to top speed this is moving, from 0.0 to 1.0
+    pub fn fraction_of_max(&self) -> f32 {
+        self.get() / self.max_speed
+    }
+
+    /// Scales the speed by `throttle` (0.0 to 1.0), for walking with a partly tilted stick
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.throttle = throttle.clamp(0.0, 1.0);
     }
 
     pub fn tick(&mut self, time: &Res<Time>) {
@@ -137,6 +149,14 @@ impl MoveSpeed {
         }
     }
 
+    /// Skips straight to full speed, for moves like long jumps and dives
+    pub fn set_to_max(&mut self) {
+        self.current_speed = self.max_speed;
+        self.state = MoveSpeedState::Accelerating;
+        self.accelerate_timer.reset();
+        self.decelerate_timer.reset();
+    }
+
     pub fn stop_moving(&mut self) {
         match self.state {
             MoveSpeedState::Accelerating => self.state = MoveSpeedState::Decelerating,
@@ -284,5 +304,6 @@ pub struct CharacterPhysicsSettings {
     pub collider_radius: f32,
 }
 
+/// Sent when a character touches the ground, along with their vertical velocity on impact
 #[derive(Event)]
-pub struct LandingEvent(pub Entity);
+pub struct LandingEvent(pub Entity, pub f32);
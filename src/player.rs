@@ -2,9 +2,16 @@ use crate::animation::{
     Animated, AnimationInit, AnimationMap, AnimationSet, AnimationTransitionEvent,
 };
 use crate::assets::{CharacterCache, PlayerAnimationCache};
-use crate::camera::CameraData;
+use crate::camera::{pixel::PixelViewport, target::CameraTarget, CameraData};
 use crate::environment::{Transition, TransitionDestination};
-use crate::input::{InputBuffer, InputListenerBundle, PlayerAction};
+use crate::input::{
+    bindings::BindingProfile,
+    combo::{ComboEvent, ComboId, ComboMatcher},
+    context::{gameplay_running, InputContextStack},
+    slots::{PlayerSlot, PlayerSlots},
+    stick::StickSettings,
+    InputBuffer, InputListenerBundle, PlayerAction,
+};
 use crate::physics::types::{
     Character, CharacterBundle, CoyoteTime, Grounded, Jumping, LandingEvent, Momentum,
     MoveDirection, MoveSpeed, Regrab,
@@ -13,6 +20,7 @@ use crate::GameState;
 use bevy::prelude::*;
 use bevy_xpbd_3d::prelude::*;
 use leafwing_input_manager::action_state::ActionState;
+use serde::{Deserialize, Serialize};
 
 pub struct PlayerPlugin;
 
@@ -27,9 +35,11 @@ impl Plugin for PlayerPlugin {
                 (
                     (
                         set_player_direction,
+                        spawn_joined_players,
                         play_idle_animation,
                         update_player_data,
-                        jump,
+                        handle_combos.before(jump).before(handle_regrab),
+                        jump.before(handle_regrab),
                         land,
                         handle_transitions,
                         handle_regrab,
@@ -40,6 +50,7 @@ impl Plugin for PlayerPlugin {
                 )
                     .chain()
                     .run_if(in_state(GameState::Overworld))
+                    .run_if(gameplay_running)
                     .before(AnimationSet),
             );
     }
@@ -51,7 +62,7 @@ pub struct LongJump;
 #[derive(Component)]
 pub struct Diving;
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect, Deserialize)]
 pub enum PlayerState {
     Diving,
     #[default]
@@ -133,10 +144,11 @@ pub struct Player;
 #[derive(Event)]
 pub struct PlayerStateTransitionEvent(pub PlayerState);
 
-#[derive(Resource, Default, Reflect)]
+#[derive(Resource, Default, Reflect, Serialize)]
 #[reflect(Resource)]
 pub struct PlayerData {
     pub player_position: Vec3,
+    pub player_rotation: Quat,
     pub player_velocity: Vec3,
     pub distance_from_floor: f32,
     pub floor_normal: Vec3,
@@ -160,25 +172,79 @@ impl PlayerData {
     }
 }
 
-fn spawn_overworld_player(mut commands: Commands, characters: Res<CharacterCache>) {
+fn spawn_player(
+    commands: &mut Commands,
+    characters: &CharacterCache,
+    bindings: &BindingProfile,
+    slots: &PlayerSlots,
+    slot: PlayerSlot,
+    transform: Transform,
+) {
     commands.spawn((
-        Name::from("Player"),
+        Name::from(format!("Player {}", slot.0 + 1)),
         SceneBundle {
             scene: characters.uli.clone_weak(),
+            transform,
             ..default()
         },
         Player,
+        slot,
         PlayerStateHandler::default(),
         CharacterBundle::default(),
         InputBuffer::default(),
-        InputListenerBundle::input_map(),
+        ComboMatcher::default(),
+        InputListenerBundle::from_profile(bindings, slots.device(slot)),
         MoveDirection::default(),
         MoveSpeed::new(200.0),
         Momentum::default(),
+        CameraTarget {
+            viewport: Some(slot.0),
+            ..default()
+        },
         Animated,
     ));
 }
 
+fn spawn_overworld_player(
+    mut commands: Commands,
+    characters: Res<CharacterCache>,
+    bindings: Res<BindingProfile>,
+    slots: Res<PlayerSlots>,
+) {
+    spawn_player(
+        &mut commands,
+        &characters,
+        &bindings,
+        &slots,
+        PlayerSlot(0),
+        Transform::default(),
+    );
+}
+
+/// Spawns a player next to the primary player whenever a new gamepad takes a slot
+fn spawn_joined_players(
+    mut commands: Commands,
+    characters: Res<CharacterCache>,
+    bindings: Res<BindingProfile>,
+    slots: Res<PlayerSlots>,
+    player_data: Res<PlayerData>,
+    player_query: Query<&PlayerSlot, With<Player>>,
+) {
+    for slot in slots.slots() {
+        if !player_query.iter().any(|spawned| *spawned == slot) {
+            let offset = Vec3::X * 1.5 * slot.0 as f32;
+            spawn_player(
+                &mut commands,
+                &characters,
+                &bindings,
+                &slots,
+                slot,
+                Transform::from_translation(player_data.player_position + offset),
+            );
+        }
+    }
+}
+
 fn play_idle_animation(
     mut commands: Commands,
     animation_map: Res<AnimationMap>,
@@ -199,44 +265,65 @@ fn play_idle_animation(
     }
 }
 
+type SteeringComponents<'a> = (
+    &'a mut MoveDirection,
+    &'a mut MoveSpeed,
+    &'a ActionState<PlayerAction>,
+    &'a Transform,
+    &'a PlayerSlot,
+);
+
 fn set_player_direction(
     camera_data: Res<CameraData>,
-    mut query: Query<(
-        &mut MoveDirection,
-        &mut MoveSpeed,
-        &ActionState<PlayerAction>,
-        Has<Grounded>,
-    )>,
+    camera_query: Query<(&CameraData, &PixelViewport)>,
+    context: Res<InputContextStack>,
+    stick_settings: Res<StickSettings>,
+    mut query: Query<SteeringComponents, With<Grounded>>,
 ) {
-    for (mut direction, mut speed, action, has_grounded) in &mut query {
-        if has_grounded {
-            if action.pressed(&PlayerAction::Move) {
-                let axis_pair = action.clamped_axis_pair(&PlayerAction::Move).unwrap();
-                let x = axis_pair.x();
-                let z = axis_pair.y();
-
-                direction.set(camera_data.translate_direction_in_camera_space(x, z));
-            } else {
-                direction.set(Vec3::ZERO);
+    for (mut direction, mut speed, action, transform, slot) in &mut query {
+        // In split screen each player moves relative to their own camera
+        let camera_data = camera_query
+            .iter()
+            .find(|(_, viewport)| viewport.0 == slot.0)
+            .map_or(camera_data.as_ref(), |(camera_data, _)| camera_data);
+        let axis_pair = match action.axis_pair(&PlayerAction::Move) {
+            Some(axis_pair) if context.top().uses_player_actions() => {
+                stick_settings.process(axis_pair.xy())
             }
+            _ => Vec2::ZERO,
+        };
+
+        if axis_pair != Vec2::ZERO {
+            direction.set(camera_data.translate_direction_for_player(
+                axis_pair.x,
+                axis_pair.y,
+                transform.translation,
+            ));
+            speed.set_throttle(stick_settings.throttle(axis_pair.length()));
+        } else {
+            direction.set(Vec3::ZERO);
+        }
 
-            if direction.started_moving() {
-                speed.start_moving();
-            }
+        if direction.started_moving() {
+            speed.start_moving();
+        }
 
-            if direction.stopped_moving() {
-                speed.stop_moving();
-            }
+        if direction.stopped_moving() {
+            speed.stop_moving();
         }
     }
 }
 
 fn update_player_data(
     mut player_data: ResMut<PlayerData>,
-    player_query: Query<(&Transform, &LinearVelocity, &MoveSpeed), With<Player>>,
+    player_query: Query<(&Transform, &LinearVelocity, &MoveSpeed, &PlayerSlot), With<Player>>,
 ) {
-    for (transform, velocity, speed) in &player_query {
+    for (transform, velocity, speed, slot) in &player_query {
+        if !slot.is_primary() {
+            continue;
+        }
         player_data.player_position = transform.translation;
+        player_data.player_rotation = transform.rotation;
         player_data.player_velocity = velocity.0;
         player_data.player_current_speed = speed.get();
     }
@@ -317,15 +404,13 @@ fn land(
     player_query: Query<Entity, With<Player>>,
     mut landing_events: EventReader<LandingEvent>,
 ) {
-    if let Ok(player_entity) = player_query.get_single() {
-        for event in landing_events.read() {
-            if event.0 == player_entity {
-                commands
-                    .entity(player_entity)
-                    .remove::<Jumping>()
-                    .remove::<LongJump>()
-                    .remove::<Diving>();
-            }
+    for event in landing_events.read() {
+        if let Ok(player_entity) = player_query.get(event.0) {
+            commands
+                .entity(player_entity)
+                .remove::<Jumping>()
+                .remove::<LongJump>()
+                .remove::<Diving>();
         }
     }
 }
@@ -336,15 +421,15 @@ fn jump(
         Entity,
         &mut LinearVelocity,
         &Character,
-        &InputBuffer,
+        &mut InputBuffer,
         Has<Grounded>,
         Has<CoyoteTime>,
     )>,
 ) {
-    for (entity, mut velocity, character, input, has_grounded, has_coyote_time) in
+    for (entity, mut velocity, character, mut input, has_grounded, has_coyote_time) in
         &mut character_query
     {
-        if (has_grounded || has_coyote_time) && input.just_pressed(PlayerAction::Jump) {
+        if (has_grounded || has_coyote_time) && input.consume(PlayerAction::Jump) {
             velocity.y = character.jump_strength;
             commands.entity(entity).insert(Jumping);
 
@@ -358,6 +443,50 @@ fn jump(
     }
 }
 
+fn handle_combos(
+    mut commands: Commands,
+    mut combo_events: EventReader<ComboEvent>,
+    mut character_query: Query<(
+        &mut LinearVelocity,
+        &mut MoveSpeed,
+        &mut InputBuffer,
+        &Character,
+    )>,
+) {
+    for event in combo_events.read() {
+        let Ok((mut velocity, mut speed, mut input, character)) =
+            character_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        match event.combo {
+            ComboId::LongJump => {
+                let is_moving = Vec2::new(velocity.x, velocity.z).length() > 1.0;
+                if is_moving && input.consume(PlayerAction::Jump) {
+                    velocity.y = character.jump_strength * 0.7;
+                    speed.set_to_max();
+                    commands
+                        .entity(event.entity)
+                        .insert(LongJump)
+                        .remove::<Grounded>();
+                }
+            }
+            ComboId::Dive => {
+                if input.consume(PlayerAction::Jump) {
+                    velocity.y = character.jump_strength * 0.4;
+                    speed.set_to_max();
+                    commands
+                        .entity(event.entity)
+                        .insert(Diving)
+                        .remove::<Jumping>()
+                        .remove::<Regrab>();
+                }
+            }
+        }
+    }
+}
+
 fn handle_regrab(
     mut commands: Commands,
     mut character_query: Query<(
@@ -365,12 +494,12 @@ fn handle_regrab(
         &mut GravityScale,
         &ShapeHits,
         &Character,
-        &InputBuffer,
+        &mut InputBuffer,
         Has<Regrab>,
         Has<Jumping>,
     )>,
 ) {
-    for (entity, mut gravity_scale, ground_hits, character, input, is_regrabbing, is_jumping) in
+    for (entity, mut gravity_scale, ground_hits, character, mut input, is_regrabbing, is_jumping) in
         &mut character_query
     {
         if (!ground_hits.is_empty() || input.released(PlayerAction::Jump)) && is_regrabbing {
@@ -378,10 +507,10 @@ fn handle_regrab(
             gravity_scale.0 = character.base_gravity_scale;
         }
 
-        if input.just_pressed(PlayerAction::Jump)
-            && ground_hits.is_empty()
+        if ground_hits.is_empty()
             && !is_jumping
             && !is_regrabbing
+            && input.consume(PlayerAction::Jump)
         {
             commands.entity(entity).insert(Regrab);
             gravity_scale.0 = character.regrab_gravity_scale;
@@ -400,9 +529,8 @@ fn handle_transitions(
     // on the current frame
     collisions: Res<Collisions>,
 ) {
-    // We know there is only one entity with the player component so we grab it with .get_single()
-    // here
-    if let Ok((player_entity, mut player_transform)) = player_query.get_single_mut() {
+    // Every local player can walk into a transition, so we check each of them
+    for (player_entity, mut player_transform) in &mut player_query {
         // Here we iterate through each collision this frame with our player entity
         for collision in collisions.collisions_with_entity(player_entity) {
             //The player could be entity1 or entity2 in the collision so this code just ensures we
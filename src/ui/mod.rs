@@ -1,14 +1,23 @@
 use crate::camera::CameraData;
+use crate::input::context::{InputContext, InputContextStack};
 use bevy::prelude::*;
 
 pub struct BaseUiPlugin;
 
 impl Plugin for BaseUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_ui.run_if(resource_exists::<CameraData>));
+        app.add_systems(Update, spawn_ui.run_if(resource_exists::<CameraData>))
+            .add_systems(
+                Update,
+                hide_hud_in_photo_mode.run_if(resource_changed::<InputContextStack>),
+            );
     }
 }
 
+/// Root of the in-game HUD
+#[derive(Component)]
+pub struct Hud;
+
 fn spawn_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -20,6 +29,7 @@ fn spawn_ui(
 
         commands
             .spawn((
+                Hud,
                 TargetCamera(camera_data.camera_id.unwrap()),
                 NodeBundle {
                     background_color: Color::NONE.into(),
@@ -45,3 +55,16 @@ fn spawn_ui(
         *has_run = true;
     }
 }
+
+fn hide_hud_in_photo_mode(
+    stack: Res<InputContextStack>,
+    mut hud_query: Query<&mut Visibility, With<Hud>>,
+) {
+    for mut visibility in &mut hud_query {
+        *visibility = if stack.is_active(InputContext::PhotoMode) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}